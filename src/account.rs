@@ -0,0 +1,30 @@
+use anyhow::{Context, Result};
+use aptos_sdk::rest_client::Account;
+use aptos_sdk::types::account_address::AccountAddress;
+
+use crate::error::ClientError;
+use crate::pool::NodePool;
+use crate::retry::RetryPolicy;
+
+// An account's onchain state, or None if it has not been created. The node answers for a
+// missing account with an error, so only that error means None; any other, such as an
+// unreachable node, is a failure
+pub async fn get(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    address: AccountAddress,
+) -> Result<Option<Account>> {
+    let result = nodes
+        .run(retry, |client| async move {
+            Ok(client.get_account(address).await?)
+        })
+        .await;
+    match result {
+        Ok(account) => Ok(Some(account.into_inner())),
+        Err(err) => match ClientError::classify(&err, retry) {
+            Some(ClientError::AccountNotFound(_)) => Ok(None),
+            _ => Err(err)
+                .with_context(|| format!("Could not fetch account {}", address.to_hex_literal())),
+        },
+    }
+}
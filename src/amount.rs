@@ -0,0 +1,248 @@
+use anyhow::{bail, Context, Result};
+use serde::de::{self, Deserialize, Deserializer};
+use serde_json::Value;
+
+// Largest number of decimals whose scale, 10^decimals, still fits in a u64
+pub const MAX_DECIMALS: u8 = 19;
+
+// How amounts of one coin are written: whole coins under the coin's symbol with `decimals`
+// fractional digits, or base units (octas for APT)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denomination {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl Denomination {
+    pub fn apt() -> Self {
+        Self {
+            symbol: "APT".to_string(),
+            decimals: 8,
+        }
+    }
+
+    // Parse an amount into base units. Accepts `1.5 APT` (or the coin's symbol) or a bare
+    // `1.5` in whole coins, and `150000000 octas` or a bare `150000000` in base units. A bare
+    // number is whole coins only if it has a decimal point, so scripts passing plain integers
+    // keep sending octas. Underscores may separate digits as in `20_000`
+    pub fn parse(&self, input: &str) -> Result<u64> {
+        let input = input.trim();
+        let split = input
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(input.len());
+        let (number, unit) = input.split_at(split);
+        let number = number.trim().replace('_', "");
+        let unit = unit.trim();
+
+        if number.is_empty() {
+            bail!("'{}' is not an amount", input);
+        }
+
+        if unit.is_empty() && number.contains('.') {
+            return self
+                .parse_whole(&number)
+                .with_context(|| format!("'{}' is not a valid {} amount", input, self.symbol));
+        }
+        if unit.is_empty()
+            || unit.eq_ignore_ascii_case("octas")
+            || unit.eq_ignore_ascii_case("octa")
+        {
+            return number
+                .parse()
+                .with_context(|| format!("'{}' is not a whole number of octas", input));
+        }
+        if !unit.eq_ignore_ascii_case(&self.symbol) {
+            bail!(
+                "'{}' has unknown unit '{}', expected {} or octas",
+                input,
+                unit,
+                self.symbol
+            );
+        }
+
+        self.parse_whole(&number)
+            .with_context(|| format!("'{}' is not a valid {} amount", input, self.symbol))
+    }
+
+    fn parse_whole(&self, number: &str) -> Result<u64> {
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            bail!("no digits");
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            bail!("only digits and one decimal point are allowed");
+        }
+        if fraction.len() > usize::from(self.decimals) {
+            bail!("more than {} decimal places", self.decimals);
+        }
+
+        let whole: u128 = match whole {
+            "" => 0,
+            whole => whole.parse().context("too large")?,
+        };
+        let fraction: u128 = match fraction {
+            "" => 0,
+            fraction => {
+                let padding = usize::from(self.decimals) - fraction.len();
+                fraction.parse::<u128>()? * 10u128.pow(padding as u32)
+            }
+        };
+
+        let base_units = whole
+            .checked_mul(self.scale())
+            .and_then(|whole| whole.checked_add(fraction))
+            .context("too large")?;
+        u64::try_from(base_units).context("too large")
+    }
+
+    // `1.5 APT`, `0.0002 APT`, `20 APT`: whole coins with trailing zeros dropped
+    pub fn format(&self, amount: u64) -> String {
+        let scale = self.scale();
+        let whole = u128::from(amount) / scale;
+        let fraction = u128::from(amount) % scale;
+
+        if fraction == 0 {
+            return format!("{} {}", whole, self.symbol);
+        }
+        let fraction = format!("{:0width$}", fraction, width = usize::from(self.decimals));
+        format!(
+            "{}.{} {}",
+            whole,
+            fraction.trim_end_matches('0'),
+            self.symbol
+        )
+    }
+
+    fn scale(&self) -> u128 {
+        10u128.pow(u32::from(self.decimals))
+    }
+}
+
+// Amounts in files may be written as numbers or as strings like "1.5 APT". Either way they
+// are kept as text until the coin they are in, and so how to parse them, is known
+pub fn deserialize_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(amount) => Ok(amount),
+        Value::Number(amount) => Ok(amount.to_string()),
+        other => Err(de::Error::custom(format!(
+            "expected an amount, got {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(decimals: u8) -> Denomination {
+        Denomination {
+            symbol: "MOON".to_string(),
+            decimals,
+        }
+    }
+
+    #[test]
+    fn whole_coins_are_read_under_their_symbol() {
+        let apt = Denomination::apt();
+
+        assert_eq!(apt.parse("1.5 APT").unwrap(), 150_000_000);
+        assert_eq!(apt.parse("0.00000001 apt").unwrap(), 1);
+        assert_eq!(apt.parse(".5 APT").unwrap(), 50_000_000);
+        assert_eq!(apt.parse("20 APT").unwrap(), 2_000_000_000);
+    }
+
+    #[test]
+    fn bare_integers_are_base_units() {
+        let apt = Denomination::apt();
+
+        assert_eq!(apt.parse("1000").unwrap(), 1_000);
+        assert_eq!(apt.parse("20_000").unwrap(), 20_000);
+        assert_eq!(apt.parse("1000 octas").unwrap(), 1_000);
+        assert_eq!(coin(6).parse("1000").unwrap(), 1_000);
+    }
+
+    #[test]
+    fn bare_decimals_are_whole_coins() {
+        let apt = Denomination::apt();
+
+        assert_eq!(apt.parse("0.01").unwrap(), 1_000_000);
+        assert_eq!(apt.parse("1.5").unwrap(), 150_000_000);
+        assert_eq!(apt.parse("20.").unwrap(), 2_000_000_000);
+        assert_eq!(coin(6).parse("0.01").unwrap(), 10_000);
+        assert!(coin(0).parse("7.5").is_err());
+        assert!(apt.parse("0.000000001").is_err());
+    }
+
+    #[test]
+    fn too_many_fractional_digits_are_refused() {
+        let err = format!("{:#}", coin(6).parse("1.0000001 MOON").unwrap_err());
+
+        assert!(err.contains("more than 6 decimal places"), "{}", err);
+        assert_eq!(coin(6).parse("1.000001 MOON").unwrap(), 1_000_001);
+    }
+
+    #[test]
+    fn amounts_past_u64_are_refused() {
+        let apt = Denomination::apt();
+
+        assert!(apt.parse("18446744073709551615").is_ok());
+        assert!(apt.parse("18446744073709551616").is_err());
+        assert!(apt.parse("184467440738 APT").is_err());
+        assert_eq!(
+            coin(MAX_DECIMALS)
+                .parse("1.8446744073709551615 MOON")
+                .unwrap(),
+            u64::MAX
+        );
+        assert!(coin(MAX_DECIMALS).parse("2 MOON").is_err());
+    }
+
+    #[test]
+    fn coins_without_decimals_only_take_whole_amounts() {
+        let whole = coin(0);
+
+        assert_eq!(whole.parse("7 MOON").unwrap(), 7);
+        assert!(whole.parse("7.5 MOON").is_err());
+        assert_eq!(whole.format(7), "7 MOON");
+    }
+
+    #[test]
+    fn unknown_units_and_garbage_are_refused() {
+        let apt = Denomination::apt();
+
+        assert!(apt.parse("1 MOON").is_err());
+        assert!(apt.parse("APT").is_err());
+        assert!(apt.parse("1.2.3 APT").is_err());
+        assert!(apt.parse("-1 APT").is_err());
+        assert!(apt.parse("1.5 octas").is_err());
+    }
+
+    #[test]
+    fn formatted_amounts_parse_back_to_themselves() {
+        for (denomination, amount) in [
+            (Denomination::apt(), 0),
+            (Denomination::apt(), 1),
+            (Denomination::apt(), 20_000),
+            (Denomination::apt(), 150_000_000),
+            (Denomination::apt(), u64::MAX),
+            (coin(0), 42),
+            (coin(6), 1_000_001),
+            (coin(MAX_DECIMALS), u64::MAX),
+        ] {
+            let formatted = denomination.format(amount);
+            assert_eq!(
+                denomination.parse(&formatted).unwrap(),
+                amount,
+                "{}",
+                formatted
+            );
+        }
+        assert_eq!(Denomination::apt().format(20_000), "0.0002 APT");
+        assert_eq!(Denomination::apt().format(150_000_000), "1.5 APT");
+    }
+}
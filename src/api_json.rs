@@ -0,0 +1,28 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+// A transaction from the SDK turned back into the JSON the node's API sent for it. Transactions
+// are read from that field by field, so the client only depends on the fields it reads and not
+// on how the SDK models every kind of transaction
+pub fn transaction<T: Serialize>(transaction: &T) -> Result<Value> {
+    serde_json::to_value(transaction).context("Could not read transaction as JSON")
+}
+
+// The API renders u64s as strings to keep JavaScript clients from losing precision
+pub fn parse_u64(value: &Value) -> Option<u64> {
+    value.as_str()?.parse().ok()
+}
+
+// A u64 field that `json` must have, with `what` naming it in the error
+pub fn u64_field(json: &Value, name: &str, what: &str) -> Result<u64> {
+    parse_u64(&json[name]).with_context(|| format!("{} has no {}", what, name))
+}
+
+// Whether an executed or simulated transaction succeeded, and the VM status saying so
+pub fn execution_status(json: &Value) -> (bool, String) {
+    (
+        json["success"].as_bool().unwrap_or(false),
+        json["vm_status"].as_str().unwrap_or_default().to_string(),
+    )
+}
@@ -0,0 +1,222 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::amount;
+
+// One payout from a batch file. Rows are numbered from 1 in the order they appear. The
+// amount is kept as written, since parsing it depends on the row's coin
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRow {
+    #[serde(skip)]
+    pub row: usize,
+    pub recipient: String,
+    #[serde(deserialize_with = "amount::deserialize_text")]
+    pub amount: String,
+    #[serde(default)]
+    pub coin_type: Option<String>,
+}
+
+// Read a batch file, either a JSON array of `{recipient, amount, coin_type}` objects or CSV
+// lines of `recipient,amount[,coin_type]`. CSV files may start with a header line and use
+// `#` comments
+pub fn read_rows(path: &Path) -> Result<Vec<BatchRow>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read batch file {}", path.display()))?;
+
+    let mut rows = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str::<Vec<BatchRow>>(&contents)
+            .with_context(|| format!("Invalid JSON batch file {}", path.display()))?,
+        _ => parse_csv(&contents)
+            .with_context(|| format!("Invalid CSV batch file {}", path.display()))?,
+    };
+    for (index, row) in rows.iter_mut().enumerate() {
+        row.row = index + 1;
+    }
+
+    Ok(rows)
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<BatchRow>> {
+    let mut rows = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if rows.is_empty() && fields[0].eq_ignore_ascii_case("recipient") {
+            continue;
+        }
+        if fields.len() < 2 || fields.len() > 3 {
+            bail!(
+                "Line {} has {} fields, expected recipient,amount[,coin_type]",
+                index + 1,
+                fields.len()
+            );
+        }
+
+        rows.push(BatchRow {
+            row: 0,
+            recipient: fields[0].to_string(),
+            amount: fields[1].to_string(),
+            coin_type: fields
+                .get(2)
+                .filter(|coin_type| !coin_type.is_empty())
+                .map(|coin_type| coin_type.to_string()),
+        });
+    }
+
+    Ok(rows)
+}
+
+// A checkpoint for one row. Every entry names the row's recipient and amount, so a rerun can
+// tell whether the file still says the same thing. `Pending` is written once a transaction is
+// signed and before it is submitted, and records the sequence number and expiration it was
+// signed with, which is enough to tell afterwards whether it ever committed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Entry {
+    Pending {
+        row: usize,
+        recipient: String,
+        amount: u64,
+        sender: String,
+        sequence_number: u64,
+        expiration_timestamp_secs: u64,
+    },
+    Committed {
+        row: usize,
+        recipient: String,
+        amount: u64,
+        hash: String,
+        version: u64,
+    },
+    Failed {
+        row: usize,
+        recipient: String,
+        amount: u64,
+        error: String,
+    },
+}
+
+impl Entry {
+    fn row(&self) -> usize {
+        match self {
+            Entry::Pending { row, .. }
+            | Entry::Committed { row, .. }
+            | Entry::Failed { row, .. } => *row,
+        }
+    }
+
+    // The recipient as written in the file and the amount in base units
+    fn payout(&self) -> (&str, u64) {
+        match self {
+            Entry::Pending {
+                recipient, amount, ..
+            }
+            | Entry::Committed {
+                recipient, amount, ..
+            }
+            | Entry::Failed {
+                recipient, amount, ..
+            } => (recipient, *amount),
+        }
+    }
+}
+
+// Append only log of checkpoints, one JSON entry per line. Every entry is synced to disk
+// before we act on it so a crash at any point leaves an accurate record behind
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    last: HashMap<usize, Entry>,
+}
+
+impl Journal {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let mut last = HashMap::new();
+        if path.exists() {
+            let file = File::open(&path)
+                .with_context(|| format!("Could not open journal {}", path.display()))?;
+            for (index, line) in BufReader::new(file).lines().enumerate() {
+                let line =
+                    line.with_context(|| format!("Could not read journal {}", path.display()))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: Entry = serde_json::from_str(&line).with_context(|| {
+                    format!(
+                        "Journal {} has an invalid entry on line {}",
+                        path.display(),
+                        index + 1
+                    )
+                })?;
+                last.insert(entry.row(), entry);
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Could not open journal {}", path.display()))?;
+
+        Ok(Self { path, file, last })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Entries are matched to rows by number alone, so once the file is edited, say by
+    // inserting or reordering a row, they would skip a new recipient as paid or pay an old one
+    // again. Refuse to go on unless every entry still matches its row of `file`, whose rows
+    // come with their amounts in base units
+    pub fn check_rows(&self, file: &Path, rows: &[(&BatchRow, u64)]) -> Result<()> {
+        let mut entries: Vec<&Entry> = self.last.values().collect();
+        entries.sort_by_key(|entry| entry.row());
+        for entry in entries {
+            let (recipient, amount) = entry.payout();
+            let matches = rows
+                .get(entry.row().wrapping_sub(1))
+                .map_or(false, |(row, row_amount)| {
+                    row.recipient == recipient && *row_amount == amount
+                });
+            if !matches {
+                bail!(
+                    "Row {} of {} does not match journal {}, which recorded {} base units to {}. \
+                     Was the file edited? Restore it or start a new journal",
+                    entry.row(),
+                    file.display(),
+                    self.path.display(),
+                    amount,
+                    recipient
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // The most recent checkpoint for a row, if it was reached in an earlier run
+    pub fn last(&self, row: usize) -> Option<&Entry> {
+        self.last.get(&row)
+    }
+
+    pub fn record(&mut self, entry: Entry) -> Result<()> {
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|()| self.file.sync_data())
+            .with_context(|| format!("Could not write journal {}", self.path.display()))?;
+        self.last.insert(entry.row(), entry);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use hdrhistogram::Histogram;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::account;
+use crate::error::ClientError;
+use crate::pool::NodePool;
+use crate::receipt;
+use crate::retry::RetryPolicy;
+use crate::transaction::{self, Status};
+
+// Longest submit to commit latency the histogram tracks, in microseconds. Anything slower is
+// recorded as this
+const MAX_LATENCY_MICROS: u64 = 60 * 60 * 1_000_000;
+
+// Open loop rates, in transfers a second, that give a usable interval between arrivals: from
+// one every 1000s up to one every microsecond
+pub const MIN_RATE: f64 = 0.001;
+pub const MAX_RATE: f64 = 1_000_000.0;
+
+// How transfers are fired at the node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Load {
+    // Open loop: start this many transfers a second whether or not earlier ones have
+    // committed, each from whichever sender is idle
+    Rate(f64),
+    // Closed loop: keep this many transfers in flight, one per sender, sending the next as
+    // soon as the last one commits
+    Concurrency(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchConfig {
+    pub load: Load,
+    // Size of the sender pool under an open loop. A closed loop has one sender per transfer
+    // in flight
+    pub senders: usize,
+    // APT minted to each sender before the run, in octas
+    pub fund_amount: u64,
+    // Sent by every transfer, in base units
+    pub amount: u64,
+    // How long to keep firing. Transfers still in flight at the end are waited for
+    pub duration: Duration,
+}
+
+impl BenchConfig {
+    pub fn senders(&self) -> usize {
+        match self.load {
+            Load::Rate(_) => self.senders,
+            Load::Concurrency(concurrency) => concurrency,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub config: BenchConfig,
+    // Accepted by the node
+    pub submitted: u64,
+    // Committed and executed successfully
+    pub committed: u64,
+    // Committed but aborted, e.g. once a sender ran out of coins
+    pub aborted: u64,
+    // Rejected by the node, or not seen to commit before the wait timeout
+    pub failed: u64,
+    // Open loop arrivals that were still waiting for an idle sender when the run ended, so
+    // were never fired
+    pub skipped: u64,
+    // Why transfers aborted or failed, with how often each happened
+    pub errors: BTreeMap<String, u64>,
+    // From the first transfer until the last one in flight settled
+    pub elapsed: Duration,
+    // Latency of committed transfers until they committed, in microseconds. Open loop
+    // transfers count from when they were due, so time spent waiting for a busy sender is
+    // part of it; closed loop ones from when they were sent
+    pub latency: Histogram<u64>,
+}
+
+impl BenchReport {
+    pub fn submitted_tps(&self) -> f64 {
+        self.submitted as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub fn committed_tps(&self) -> f64 {
+        self.committed as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+// Generates load against one network's nodes, from a pool of fresh senders funded by its
+// faucet. The senders are thrown away afterwards, along with whatever coins they have left
+pub struct Bench<'a> {
+    pub nodes: &'a NodePool,
+    pub faucet_client: FaucetClient,
+    pub options: TransferOptions<'a>,
+    pub wait_timeout: Duration,
+    pub retry: RetryPolicy,
+}
+
+// A pooled sender and who it pays. Each sender pays the next one in the pool so every
+// recipient already exists
+struct PoolSender {
+    account: LocalAccount,
+    recipient: AccountAddress,
+}
+
+impl<'a> Bench<'a> {
+    pub async fn run(&self, config: &BenchConfig) -> Result<BenchReport> {
+        if let Load::Rate(rate) = config.load {
+            if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+                bail!(
+                    "A bench rate must be between {} and {} transfers a second, not {}",
+                    MIN_RATE,
+                    MAX_RATE,
+                    rate
+                );
+            }
+        }
+        let pool = self.fund_pool(config).await?;
+        let transfers = Transfers {
+            nodes: self.nodes.clone(),
+            coin_type: self.options.coin_type.to_string(),
+            max_gas_amount: self.options.max_gas_amount,
+            gas_unit_price: self.options.gas_unit_price,
+            timeout_secs: self.options.timeout_secs,
+            wait_timeout: self.wait_timeout,
+            retry: self.retry.clone(),
+            amount: config.amount,
+            stats: Arc::new(Mutex::new(Stats {
+                submitted: 0,
+                committed: 0,
+                aborted: 0,
+                failed: 0,
+                skipped: 0,
+                errors: BTreeMap::new(),
+                latency: Histogram::new_with_bounds(1, MAX_LATENCY_MICROS, 3)
+                    .expect("latency histogram bounds are valid"),
+            })),
+        };
+
+        let started = Instant::now();
+        let deadline = started + config.duration;
+        let handles = match config.load {
+            Load::Rate(rate) => transfers.open_loop(pool, rate, started, deadline).await,
+            Load::Concurrency(_) => pool
+                .into_iter()
+                .map(|sender| tokio::spawn(transfers.clone().closed_loop(sender, deadline)))
+                .collect(),
+        };
+        for handle in handles {
+            handle.await.context("Bench transfer task panicked")?;
+        }
+        let elapsed = started.elapsed();
+
+        let stats = transfers.stats.lock().unwrap();
+        Ok(BenchReport {
+            config: config.clone(),
+            submitted: stats.submitted,
+            committed: stats.committed,
+            aborted: stats.aborted,
+            failed: stats.failed,
+            skipped: stats.skipped,
+            errors: stats.errors.clone(),
+            elapsed,
+            latency: stats.latency.clone(),
+        })
+    }
+
+    async fn fund_pool(&self, config: &BenchConfig) -> Result<Vec<PoolSender>> {
+        let count = config.senders();
+        if count == 0 {
+            bail!("A bench needs at least one sender");
+        }
+
+        let accounts: Vec<_> = (0..count)
+            .map(|_| LocalAccount::generate(&mut rand::rngs::OsRng))
+            .collect();
+        for (index, account) in accounts.iter().enumerate() {
+            let address = account.address();
+            self.retry
+                .run(|| self.faucet_client.fund(address, config.fund_amount))
+                .await
+                .map_err(ClientError::faucet)
+                .with_context(|| format!("Failed to fund bench sender {}", index + 1))?;
+        }
+
+        let addresses: Vec<_> = accounts.iter().map(LocalAccount::address).collect();
+        Ok(accounts
+            .into_iter()
+            .enumerate()
+            .map(|(index, account)| PoolSender {
+                account,
+                recipient: addresses[(index + 1) % addresses.len()],
+            })
+            .collect())
+    }
+}
+
+// Open loop senders with nothing to send, and arrivals that came while none were idle, oldest
+// first
+struct Queue {
+    idle: Vec<PoolSender>,
+    waiting: VecDeque<Instant>,
+}
+
+struct Stats {
+    submitted: u64,
+    committed: u64,
+    aborted: u64,
+    failed: u64,
+    skipped: u64,
+    errors: BTreeMap<String, u64>,
+    latency: Histogram<u64>,
+}
+
+// Everything a spawned transfer task needs, owned so the task can outlive the borrow of the
+// bench it came from
+#[derive(Clone)]
+struct Transfers {
+    nodes: NodePool,
+    coin_type: String,
+    max_gas_amount: u64,
+    gas_unit_price: u64,
+    timeout_secs: u64,
+    wait_timeout: Duration,
+    retry: RetryPolicy,
+    amount: u64,
+    stats: Arc<Mutex<Stats>>,
+}
+
+impl Transfers {
+    // Arrivals are due at fixed times whatever the node does. One that finds every sender busy
+    // waits for the next sender to come free, and its latency counts from when it was due, so
+    // a slow node cannot thin out the load it is measured under
+    async fn open_loop(
+        &self,
+        pool: Vec<PoolSender>,
+        rate: f64,
+        started: Instant,
+        deadline: Instant,
+    ) -> Vec<JoinHandle<()>> {
+        let queue = Arc::new(Mutex::new(Queue {
+            idle: pool,
+            waiting: VecDeque::new(),
+        }));
+        let period_nanos = (1e9 / rate).round() as u64;
+
+        let mut handles = Vec::new();
+        for arrival in 0u64.. {
+            let due = started + Duration::from_nanos(period_nanos.saturating_mul(arrival));
+            if due >= deadline {
+                break;
+            }
+            tokio::time::sleep_until(due).await;
+
+            let sender = {
+                let mut queue = queue.lock().unwrap();
+                match queue.idle.pop() {
+                    Some(sender) => sender,
+                    None => {
+                        queue.waiting.push_back(due);
+                        continue;
+                    }
+                }
+            };
+            let transfers = self.clone();
+            let queue = queue.clone();
+            handles.push(tokio::spawn(async move {
+                transfers.serve(sender, due, &queue, deadline).await
+            }));
+        }
+
+        // Senders take no more waiting arrivals once the run is over
+        tokio::time::sleep_until(deadline).await;
+        let missed = queue.lock().unwrap().waiting.drain(..).count();
+        self.stats.lock().unwrap().skipped += missed as u64;
+
+        handles
+    }
+
+    // Send the arrival due at `due`, then any that queued up while the sender was busy, until
+    // none are waiting or the run is over
+    async fn serve(
+        &self,
+        mut sender: PoolSender,
+        mut due: Instant,
+        queue: &Mutex<Queue>,
+        deadline: Instant,
+    ) {
+        loop {
+            sender = self.send(sender, due).await;
+
+            let mut queue = queue.lock().unwrap();
+            let next = match Instant::now() < deadline {
+                true => queue.waiting.pop_front(),
+                false => None,
+            };
+            match next {
+                Some(next) => due = next,
+                None => {
+                    queue.idle.push(sender);
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn closed_loop(self, mut sender: PoolSender, deadline: Instant) {
+        while Instant::now() < deadline {
+            sender = self.send(sender, Instant::now()).await;
+        }
+    }
+
+    // Send one transfer and wait for it to commit, keeping count of how it went and timing it
+    // from `due`. The sender comes back ready for its next transfer
+    async fn send(&self, mut sender: PoolSender, due: Instant) -> PoolSender {
+        let options = TransferOptions {
+            coin_type: &self.coin_type,
+            max_gas_amount: self.max_gas_amount,
+            gas_unit_price: self.gas_unit_price,
+            timeout_secs: self.timeout_secs,
+        };
+
+        let signed = match transaction::sign_transfer(
+            &self.nodes,
+            &self.retry,
+            &mut sender.account,
+            sender.recipient,
+            self.amount,
+            &options,
+        )
+        .await
+        {
+            Ok(signed) => signed,
+            Err(err) => {
+                self.failed(&err);
+                self.resync(&mut sender).await;
+                return sender;
+            }
+        };
+        let pending = match transaction::submit(&self.nodes, &self.retry, &signed).await {
+            Ok(pending) => pending,
+            Err(err) => {
+                self.failed(&err);
+                self.resync(&mut sender).await;
+                return sender;
+            }
+        };
+        self.stats.lock().unwrap().submitted += 1;
+
+        match receipt::wait(&self.nodes, &self.retry, &pending, self.wait_timeout).await {
+            Ok(receipt) if receipt.success => {
+                let micros = u64::try_from(due.elapsed().as_micros()).unwrap_or(u64::MAX);
+                let mut stats = self.stats.lock().unwrap();
+                stats.committed += 1;
+                stats.latency.saturating_record(micros.max(1));
+            }
+            Ok(receipt) => {
+                let mut stats = self.stats.lock().unwrap();
+                stats.aborted += 1;
+                *stats.errors.entry(receipt.vm_status).or_insert(0) += 1;
+            }
+            // A transfer that is only slow may still commit, and resyncing then would hand
+            // its sequence number out again. Only one confirmed to have expired left a gap
+            Err(err) => {
+                self.failed(&err);
+                let status = transaction::status(
+                    &self.nodes,
+                    &self.retry,
+                    sender.account.address(),
+                    signed.sequence_number(),
+                    signed.expiration_timestamp_secs(),
+                )
+                .await;
+                if let Ok(Status::Expired) = status {
+                    self.resync(&mut sender).await;
+                }
+            }
+        }
+
+        sender
+    }
+
+    // Errors are counted by their root cause, which leaves out per transaction details such
+    // as hashes
+    fn failed(&self, err: &anyhow::Error) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.failed += 1;
+        *stats
+            .errors
+            .entry(err.root_cause().to_string())
+            .or_insert(0) += 1;
+    }
+
+    // After a failure the local sequence number may be ahead of the chain's. If the chain
+    // cannot be asked either, keep the local one and let the next transfer find out
+    async fn resync(&self, sender: &mut PoolSender) {
+        let address = sender.account.address();
+        if let Ok(Some(onchain)) = account::get(&self.nodes, &self.retry, address).await {
+            *sender.account.sequence_number_mut() = onchain.sequence_number;
+        }
+    }
+}
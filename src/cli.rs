@@ -0,0 +1,306 @@
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "aptos-client",
+    version,
+    about = "Send and inspect coins on an Aptos network",
+    after_help = "EXIT CODES:\n    0  success\n    1  any other error\n    2  invalid usage\n    3  network error\n    4  faucet error\n    5  insufficient balance\n    6  sequence number mismatch\n    7  transaction expired\n    8  account not found\n    9  Move abort\n    10 timed out waiting for a transaction\n    11 transaction failed"
+)]
+pub struct Cli {
+    /// Directory holding named accounts [default: $APTOS_KEYSTORE or ~/.aptos-client/keystore]
+    #[clap(long, global = true)]
+    pub keystore: Option<PathBuf>,
+
+    /// File holding the keystore password [default: $APTOS_KEYSTORE_PASSWORD or prompt]
+    #[clap(long, global = true)]
+    pub password_file: Option<PathBuf>,
+
+    /// Config file [default: $APTOS_CONFIG or ~/.aptos-client/config.toml if present]
+    #[clap(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Network profile: devnet, testnet, mainnet, local or custom [default: devnet]
+    #[clap(long, global = true)]
+    pub network: Option<String>,
+
+    /// Node URL, overriding the network profile's. Repeat it to fail over between several nodes
+    #[clap(long, global = true)]
+    pub node_url: Vec<String>,
+
+    /// Faucet URL, overriding the network profile's
+    #[clap(long, global = true)]
+    pub faucet_url: Option<String>,
+
+    /// Chain id the node must report, overriding the network profile's
+    #[clap(long, global = true)]
+    pub chain_id: Option<u8>,
+
+    /// Versions a node may be behind the newest node and still take requests [default: 1000]
+    #[clap(long, global = true)]
+    pub max_lag_versions: Option<u64>,
+
+    /// Output format: table, json, ndjson or csv [default: table]. Key management, scan,
+    /// build, sign and explain only print tables
+    #[clap(long, global = true)]
+    pub output: Option<String>,
+
+    /// Seconds to wait for a submitted transaction to commit [default: 60]
+    #[clap(long, global = true)]
+    pub wait_timeout_secs: Option<u64>,
+
+    /// Coin to transfer [default: 0x1::aptos_coin::AptosCoin]
+    #[clap(long, global = true)]
+    pub coin_type: Option<String>,
+
+    /// Decimals to parse and show amounts with [default: APT's 8, or the coin's CoinInfo]
+    #[clap(long, global = true)]
+    pub decimals: Option<u8>,
+
+    /// Maximum gas units a transfer may use [default: SDK default]
+    #[clap(long, global = true)]
+    pub max_gas_amount: Option<u64>,
+
+    /// Octas paid per gas unit [default: SDK default]
+    #[clap(long, global = true)]
+    pub gas_unit_price: Option<u64>,
+
+    /// Seconds until a signed transfer expires if it has not committed [default: SDK default, an hour for `build`]
+    #[clap(long, global = true)]
+    pub expiration_secs: Option<u64>,
+
+    /// Tries in all for node and faucet calls that fail transiently, 1 to never retry [default: 4]
+    #[clap(long, global = true)]
+    pub max_attempts: Option<u32>,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Transfer coins from a keystore account or private key to an address
+    Transfer(TransferArgs),
+    /// Send the payouts listed in a CSV or JSON file, resuming from its journal if present
+    Batch(BatchArgs),
+    /// Build an unsigned transfer for offline signing, with the sender's current sequence number
+    Build(BuildArgs),
+    /// Sign a transaction file written by `build`, without touching the network
+    Sign(SignArgs),
+    /// Submit a transaction file signed by `sign` and wait for it to commit
+    Submit(SubmitArgs),
+    /// Decode a transaction file or an onchain transaction and say what it does
+    Explain(ExplainArgs),
+    /// Print the coin balance of an address
+    Balance(BalanceArgs),
+    /// Fund an address through the faucet, creating the account if needed
+    Fund(FundArgs),
+    /// Create an onchain account for an address through the faucet
+    CreateAccount(CreateAccountArgs),
+    /// Generate a new local account, or derive one from a mnemonic, and print its keys
+    Keygen(KeygenArgs),
+    /// List the accounts saved in the keystore
+    Accounts,
+    /// List which addresses derived from a mnemonic already exist onchain
+    Scan(ScanArgs),
+    /// Import a private key or an exported key file into the keystore
+    Import(ImportArgs),
+    /// Export a keystore account as an encrypted key file or a plaintext private key
+    Export(ExportArgs),
+    /// Re-encrypt a keystore account under a new password
+    ChangePassword(ChangePasswordArgs),
+    /// Run the Alice -> Bob demo flow with the keystore's `alice` and `bob` accounts
+    Demo,
+    /// Run a YAML scenario file with fresh accounts, failing if any step or assertion fails
+    RunScenario(RunScenarioArgs),
+    /// Generate transfer load from a pool of faucet funded senders and report TPS and latency
+    Bench(BenchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TransferArgs {
+    /// Keystore account name or hex encoded ed25519 private key of the sender [default: default_sender from config]
+    #[clap(long)]
+    pub from: Option<String>,
+    /// Keystore account name or address of the recipient
+    #[clap(long)]
+    pub to: String,
+    /// Amount to send: `1.5 APT` (or the coin's symbol) or a bare `1.5` in whole coins, or `150000000 octas` or a bare `150000000` in base units
+    #[clap(long)]
+    pub amount: String,
+    /// Simulate the signed transfer and report its effects instead of submitting it
+    #[clap(long)]
+    pub simulate: bool,
+    /// Create the recipient's onchain account through the faucet if it does not exist yet
+    #[clap(long)]
+    pub auto_create: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct BuildArgs {
+    /// Keystore account name or address of the sender [default: default_sender from config]
+    #[clap(long)]
+    pub from: Option<String>,
+    /// Keystore account name or address of the recipient
+    #[clap(long)]
+    pub to: String,
+    /// Amount to send: `1.5 APT` (or the coin's symbol) or a bare `1.5` in whole coins, or `150000000 octas` or a bare `150000000` in base units
+    #[clap(long)]
+    pub amount: String,
+    /// File to write the unsigned transaction to. It expires an hour after it is built unless --expiration-secs says otherwise
+    #[clap(long)]
+    pub output_file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct SignArgs {
+    /// Transaction file written by `build`
+    pub file: PathBuf,
+    /// Keystore account name or hex encoded ed25519 private key of the sender
+    #[clap(long)]
+    pub key: String,
+    /// File to write the signed transaction to
+    #[clap(long)]
+    pub output_file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct SubmitArgs {
+    /// Transaction file written by `sign`
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct ExplainArgs {
+    /// A file from `build` or `sign`, a signed transaction as BCS (binary or hex), or the hash of a transaction on the node
+    pub transaction: String,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Keystore account name or hex encoded ed25519 private key of the sender [default: default_sender from config]
+    #[clap(long)]
+    pub from: Option<String>,
+    /// CSV file of `recipient,amount[,coin_type]` rows, or a JSON array of objects with those fields
+    pub file: PathBuf,
+    /// Checkpoint journal used to resume an interrupted run [default: <file>.journal]
+    #[clap(long)]
+    pub journal: Option<PathBuf>,
+    /// Create recipients' onchain accounts through the faucet if they do not exist yet
+    #[clap(long)]
+    pub auto_create: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RunScenarioArgs {
+    /// YAML file declaring the accounts and the steps to run
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Open loop: start this many transfers a second, from 0.001 to 1000000, whether or not earlier ones committed
+    #[clap(long, conflicts_with = "concurrency")]
+    pub rate: Option<f64>,
+    /// Closed loop: keep this many transfers in flight, one per sender [default: 8]
+    #[clap(long)]
+    pub concurrency: Option<usize>,
+    /// Number of senders an open loop fires from
+    #[clap(long, default_value_t = 16, requires = "rate")]
+    pub senders: usize,
+    /// APT minted to each sender before the run: `1.5 APT` or a bare `1.5`, or `150000000 octas` or a bare `150000000` in octas
+    #[clap(long, default_value = "0.1 APT")]
+    pub fund: String,
+    /// Amount each transfer sends
+    #[clap(long, default_value = "1 octas")]
+    pub amount: String,
+    /// Seconds to keep firing transfers
+    #[clap(long, default_value_t = 30)]
+    pub duration_secs: u64,
+    /// Also write the latency histogram here as an HdrHistogram interval log
+    #[clap(long)]
+    pub histogram_log: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct BalanceArgs {
+    /// Keystore account name or address
+    pub address: String,
+}
+
+#[derive(Debug, Args)]
+pub struct FundArgs {
+    /// Keystore account name or address
+    pub address: String,
+    /// Amount of APT to mint: `1.5 APT` or a bare `1.5`, or `150000000 octas` or a bare `150000000` in octas
+    pub amount: String,
+}
+
+#[derive(Debug, Args)]
+pub struct CreateAccountArgs {
+    /// Keystore account name or address
+    pub address: String,
+}
+
+#[derive(Debug, Args)]
+pub struct KeygenArgs {
+    /// Save the generated account in the keystore under this name
+    #[clap(long)]
+    pub name: Option<String>,
+    /// Derive the account from a BIP-39 mnemonic instead of generating a random key
+    #[clap(long)]
+    pub mnemonic: bool,
+    /// File holding the mnemonic phrase [default: $APTOS_MNEMONIC or prompt]
+    #[clap(long, requires = "mnemonic")]
+    pub mnemonic_file: Option<PathBuf>,
+    /// Account index n in the derivation path m/44'/637'/n'/0'/0'
+    #[clap(long, default_value_t = 0, requires = "mnemonic")]
+    pub index: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct ScanArgs {
+    /// File holding the mnemonic phrase [default: $APTOS_MNEMONIC or prompt]
+    #[clap(long)]
+    pub mnemonic_file: Option<PathBuf>,
+    /// First account index to derive
+    #[clap(long, default_value_t = 0)]
+    pub start: u32,
+    /// Number of consecutive indices to derive
+    #[clap(long, default_value_t = 10)]
+    pub count: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// Name to save the account under
+    pub name: String,
+    /// File holding a hex encoded private key [default: prompt for the key]
+    #[clap(long, conflicts_with = "key_file")]
+    pub private_key_file: Option<PathBuf>,
+    /// Encrypted key file written by `export`, decrypted with the keystore password
+    #[clap(long)]
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Keystore account name
+    pub name: String,
+    /// Write the key file here instead of printing it
+    #[clap(long)]
+    pub output_file: Option<PathBuf>,
+    /// Export the decrypted private key as hex instead of an encrypted key file
+    #[clap(long)]
+    pub plaintext: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ChangePasswordArgs {
+    /// Keystore account name
+    pub name: String,
+    /// File holding the new password [default: $APTOS_KEYSTORE_NEW_PASSWORD or prompt]
+    #[clap(long)]
+    pub new_password_file: Option<PathBuf>,
+}
@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use aptos_sdk::types::account_address::AccountAddress;
+use serde_json::Value;
+use std::convert::TryFrom;
+
+use crate::amount::{Denomination, MAX_DECIMALS};
+use crate::config::APTOS_COIN_TYPE;
+use crate::pool::NodePool;
+use crate::retry::RetryPolicy;
+
+// The resource holding an account's coins of one type
+pub fn coin_store_type(coin_type: &str) -> String {
+    format!("0x1::coin::CoinStore<{}>", coin_type)
+}
+
+// Balance of any coin type, read straight from the account's CoinStore. Returns None if the
+// account has not registered the coin (or does not exist)
+pub async fn balance(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    address: AccountAddress,
+    coin_type: &str,
+) -> Result<Option<u64>> {
+    let store_type = &coin_store_type(coin_type);
+    let resource = nodes
+        .run(retry, |client| async move {
+            Ok(client.get_account_resource(address, store_type).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch balance of {}", address.to_hex_literal()))?
+        .into_inner();
+
+    let resource = match resource {
+        Some(resource) => resource,
+        None => return Ok(None),
+    };
+    let value = coin_store_value(&resource.data).with_context(|| {
+        format!(
+            "Unexpected CoinStore resource for {}",
+            address.to_hex_literal()
+        )
+    })?;
+
+    Ok(Some(value))
+}
+
+// The `coin.value` field of a CoinStore resource as the API renders it, a u64 in a string
+pub fn coin_store_value(data: &Value) -> Option<u64> {
+    data.get("coin")?.get("value")?.as_str()?.parse().ok()
+}
+
+// Symbol and decimals of a coin. APT's are fixed; any other coin describes itself in the
+// CoinInfo resource published under the account that defines it
+pub async fn denomination(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    coin_type: &str,
+) -> Result<Denomination> {
+    if coin_type == APTOS_COIN_TYPE {
+        return Ok(Denomination::apt());
+    }
+
+    let address = coin_type
+        .split("::")
+        .next()
+        .and_then(|address| AccountAddress::from_hex_literal(address).ok())
+        .with_context(|| format!("Coin type '{}' has no valid address", coin_type))?;
+    let info_type = &format!("0x1::coin::CoinInfo<{}>", coin_type);
+    let info = nodes
+        .run(retry, |client| async move {
+            Ok(client.get_account_resource(address, info_type).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch CoinInfo for {}", coin_type))?
+        .into_inner()
+        .with_context(|| format!("{} is not an initialized coin", coin_type))?;
+
+    from_coin_info(coin_type, &info.data)
+}
+
+// A denomination from the fields of a CoinInfo resource, which renders decimals as a number
+// or a string depending on the API version
+fn from_coin_info(coin_type: &str, data: &Value) -> Result<Denomination> {
+    let symbol = data["symbol"]
+        .as_str()
+        .with_context(|| format!("CoinInfo for {} has no symbol", coin_type))?;
+    let decimals = match &data["decimals"] {
+        Value::Number(decimals) => decimals.as_u64(),
+        Value::String(decimals) => decimals.parse().ok(),
+        _ => None,
+    }
+    .and_then(|decimals| u8::try_from(decimals).ok())
+    .filter(|decimals| *decimals <= MAX_DECIMALS)
+    .with_context(|| format!("CoinInfo for {} has invalid decimals", coin_type))?;
+
+    Ok(Denomination {
+        symbol: symbol.to_string(),
+        decimals,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MOON_COIN: &str = "0xcafe::moon_coin::MoonCoin";
+
+    #[test]
+    fn coin_info_decimals_may_be_numbers_or_strings() {
+        let expected = Denomination {
+            symbol: "MOON".to_string(),
+            decimals: 6,
+        };
+
+        let number = json!({ "symbol": "MOON", "decimals": 6 });
+        let string = json!({ "symbol": "MOON", "decimals": "6" });
+
+        assert_eq!(from_coin_info(MOON_COIN, &number).unwrap(), expected);
+        assert_eq!(from_coin_info(MOON_COIN, &string).unwrap(), expected);
+    }
+
+    #[test]
+    fn coin_info_with_zero_decimals_counts_whole_coins() {
+        let info = json!({ "symbol": "MOON", "decimals": 0 });
+
+        let denomination = from_coin_info(MOON_COIN, &info).unwrap();
+
+        assert_eq!(denomination.decimals, 0);
+        assert_eq!(denomination.parse("3 MOON").unwrap(), 3);
+        assert_eq!(denomination.format(3), "3 MOON");
+    }
+
+    #[test]
+    fn coin_info_with_invalid_decimals_is_refused() {
+        for decimals in [
+            json!(20),
+            json!("255"),
+            json!(-1),
+            json!("six"),
+            json!(null),
+        ] {
+            let info = json!({ "symbol": "MOON", "decimals": decimals });
+
+            let err = from_coin_info(MOON_COIN, &info).unwrap_err();
+
+            assert!(err.to_string().contains("invalid decimals"), "{}", err);
+        }
+        let info = json!({ "symbol": "MOON", "decimals": MAX_DECIMALS });
+        assert!(from_coin_info(MOON_COIN, &info).is_ok());
+    }
+
+    #[test]
+    fn coin_info_without_a_symbol_is_refused() {
+        let info = json!({ "decimals": 6 });
+
+        assert!(from_coin_info(MOON_COIN, &info).is_err());
+    }
+
+    #[test]
+    fn coin_store_value_is_read_from_its_string() {
+        let store = json!({ "coin": { "value": "20000" } });
+
+        assert_eq!(coin_store_value(&store), Some(20_000));
+        assert_eq!(
+            coin_store_value(&json!({ "coin": { "value": 20000 } })),
+            None
+        );
+        assert_eq!(
+            coin_store_type(MOON_COIN),
+            "0x1::coin::CoinStore<0xcafe::moon_coin::MoonCoin>"
+        );
+    }
+}
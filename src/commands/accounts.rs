@@ -0,0 +1,18 @@
+use anyhow::Result;
+
+use super::App;
+
+pub fn run(app: &App) -> Result<()> {
+    for account in app.keystore.list()? {
+        let encryption = match account.is_encrypted() {
+            true => "encrypted",
+            false => "plaintext",
+        };
+        println!(
+            "{}: {} (sequence number {}, {})",
+            account.name, account.address, account.sequence_number, encryption
+        );
+    }
+
+    Ok(())
+}
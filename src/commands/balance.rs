@@ -0,0 +1,28 @@
+use anyhow::Result;
+
+use super::App;
+use crate::cli::BalanceArgs;
+use crate::coin;
+use crate::output::Record;
+
+pub async fn run(app: &App, args: BalanceArgs) -> Result<()> {
+    let address = app.resolve_address(&args.address)?;
+    let coin_type = &app.config.coin_type;
+    let denomination = app.denomination(coin_type).await?;
+
+    // An account that never registered the coin simply holds none of it
+    let balance = coin::balance(&app.nodes, &app.config.retry, address, coin_type)
+        .await?
+        .unwrap_or(0);
+
+    app.output.balances(
+        None,
+        &denomination,
+        vec![Record::balance(
+            None,
+            &args.address,
+            address.to_hex_literal(),
+            balance,
+        )],
+    )
+}
@@ -0,0 +1,448 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::types::account_address::AccountAddress;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{App, Sender};
+use crate::amount::Denomination;
+use crate::batch::{self, BatchRow, Entry, Journal};
+use crate::cli::BatchArgs;
+use crate::output::{Record, RecordKind};
+use crate::preflight;
+use crate::receipt::Receipt;
+use crate::transaction::{self, Status};
+
+// What happened to a row in this run. `created` if the recipient's account was made first
+enum RowResult {
+    Paid { receipt: Receipt, created: bool },
+    AlreadyPaid { hash: String },
+    Failed { error: String },
+}
+
+// What became of a transaction that may or may not have reached the chain
+enum Outcome {
+    Committed { receipt: Receipt },
+    Failed { error: String },
+}
+
+pub async fn run(app: &App, args: BatchArgs) -> Result<()> {
+    let rows = batch::read_rows(&args.file)?;
+    let amounts = parse_amounts(app, &rows).await?;
+    let journal_path = args.journal.unwrap_or_else(|| {
+        let mut path = args.file.clone().into_os_string();
+        path.push(".journal");
+        path.into()
+    });
+    let mut journal = Journal::open(journal_path)?;
+    let payouts: Vec<_> = rows
+        .iter()
+        .zip(amounts.iter().map(|(amount, _)| *amount))
+        .collect();
+    journal.check_rows(&args.file, &payouts)?;
+    let faucet_client = match args.auto_create {
+        true => Some(app.faucet_client()?),
+        false => None,
+    };
+
+    // The journal relies on sequence numbers to tell what was sent, so start from the
+    // onchain value rather than whatever the keystore last saw
+    let mut sender = app
+        .load_sender(app.sender_name(args.from.as_deref())?)
+        .await?;
+    resync_sequence_number(app, &mut sender).await?;
+
+    let mut results = Vec::with_capacity(rows.len());
+    for (row, (amount, _)) in rows.iter().zip(&amounts) {
+        let amount = *amount;
+        let recipient = match app.resolve_address(&row.recipient) {
+            Ok(recipient) => recipient,
+            Err(err) => {
+                results.push(RowResult::Failed {
+                    error: format!("{:#}", err),
+                });
+                continue;
+            }
+        };
+
+        // Settle whatever an earlier run left behind for this row before sending anything
+        match journal.last(row.row).cloned() {
+            Some(Entry::Committed { hash, .. }) => {
+                results.push(RowResult::AlreadyPaid { hash });
+                continue;
+            }
+            Some(Entry::Pending {
+                sender: journal_sender,
+                sequence_number,
+                expiration_timestamp_secs,
+                ..
+            }) => {
+                if journal_sender != sender.account.address().to_hex_literal() {
+                    bail!(
+                        "Journal {} was written for sender {}, rerun the batch from that account",
+                        journal.path().display(),
+                        journal_sender
+                    );
+                }
+
+                let outcome = reconcile(
+                    app,
+                    sender.account.address(),
+                    sequence_number,
+                    expiration_timestamp_secs,
+                )
+                .await?;
+                resync_sequence_number(app, &mut sender).await?;
+                if let Outcome::Committed { receipt } = outcome {
+                    journal.record(Entry::Committed {
+                        row: row.row,
+                        recipient: row.recipient.clone(),
+                        amount,
+                        hash: receipt.hash.clone(),
+                        version: receipt.version,
+                    })?;
+                    results.push(RowResult::AlreadyPaid { hash: receipt.hash });
+                    continue;
+                }
+            }
+            Some(Entry::Failed { .. }) | None => {}
+        }
+
+        let result = send(
+            app,
+            &mut journal,
+            &mut sender,
+            row,
+            recipient,
+            amount,
+            faucet_client.as_ref(),
+        )
+        .await?;
+        results.push(result);
+    }
+
+    match app.output.is_table() {
+        true => print_report(&rows, &amounts, &results),
+        false => emit_records(app, &rows, &amounts, &results)?,
+    }
+
+    let failed = results
+        .iter()
+        .filter(|result| matches!(result, RowResult::Failed { .. }))
+        .count();
+    if failed > 0 {
+        bail!(
+            "{} of {} transfers failed, rerun to retry them",
+            failed,
+            rows.len()
+        );
+    }
+
+    Ok(())
+}
+
+// Parse every row's amount in its coin's denomination before anything is sent, so a typo
+// halfway down the file cannot leave a batch partly paid. Each amount comes back in base
+// units alongside how it is displayed
+async fn parse_amounts(app: &App, rows: &[BatchRow]) -> Result<Vec<(u64, String)>> {
+    let mut denominations: HashMap<&str, Denomination> = HashMap::new();
+    let mut amounts = Vec::with_capacity(rows.len());
+    for row in rows {
+        let coin_type = row.coin_type.as_deref().unwrap_or(&app.config.coin_type);
+        if !denominations.contains_key(coin_type) {
+            denominations.insert(coin_type, app.denomination(coin_type).await?);
+        }
+        let denomination = &denominations[coin_type];
+
+        let amount = denomination
+            .parse(&row.amount)
+            .with_context(|| format!("Row {} has an invalid amount", row.row))?;
+        amounts.push((amount, denomination.format(amount)));
+    }
+
+    Ok(amounts)
+}
+
+async fn send(
+    app: &App,
+    journal: &mut Journal,
+    sender: &mut Sender,
+    row: &BatchRow,
+    recipient: AccountAddress,
+    amount: u64,
+    auto_create: Option<&FaucetClient>,
+) -> Result<RowResult> {
+    let defaults = app.transfer_options();
+    let options = TransferOptions {
+        coin_type: row.coin_type.as_deref().unwrap_or(defaults.coin_type),
+        ..defaults
+    };
+
+    // Catch what would make the row fail before it uses up a sequence number
+    let report = match preflight::check(
+        &app.nodes,
+        &app.config.retry,
+        sender.account.address(),
+        recipient,
+        amount,
+        &options,
+        auto_create,
+    )
+    .await
+    {
+        Ok(report) => report,
+        Err(err) => return not_sent(journal, row, amount, format!("{:#}", err)),
+    };
+
+    // The journal takes the sequence number and expiration from the signed transaction
+    // itself, since those are what decide whether it can still commit
+    let signed = match transaction::sign_transfer(
+        &app.nodes,
+        &app.config.retry,
+        &mut sender.account,
+        recipient,
+        amount,
+        &options,
+    )
+    .await
+    {
+        Ok(signed) => signed,
+        Err(err) => {
+            let error = format!("{:#}", err.context("Failed to sign transfer"));
+            return not_sent(journal, row, amount, error);
+        }
+    };
+    let sequence_number = signed.sequence_number();
+    let expiration_timestamp_secs = signed.expiration_timestamp_secs();
+    journal.record(Entry::Pending {
+        row: row.row,
+        recipient: row.recipient.clone(),
+        amount,
+        sender: sender.account.address().to_hex_literal(),
+        sequence_number,
+        expiration_timestamp_secs,
+    })?;
+    app.save_sender(sender)?;
+
+    let committed = async {
+        let pending = transaction::submit(&app.nodes, &app.config.retry, &signed)
+            .await
+            .context("Failed to transfer coins")?;
+        app.wait_for_transaction(&pending).await
+    }
+    .await;
+
+    // Anything short of a committed transaction leaves its fate unclear, for example a
+    // submission that timed out may still be in mempool, so ask the chain
+    let outcome = match committed {
+        Ok(receipt) => committed_outcome(receipt),
+        Err(err) => {
+            let outcome = reconcile(
+                app,
+                sender.account.address(),
+                sequence_number,
+                expiration_timestamp_secs,
+            )
+            .await?;
+            resync_sequence_number(app, sender).await?;
+            match outcome {
+                Outcome::Failed { error } => Outcome::Failed {
+                    error: format!("{:#}: {}", err, error),
+                },
+                committed => committed,
+            }
+        }
+    };
+
+    Ok(match outcome {
+        Outcome::Committed { receipt } => {
+            journal.record(Entry::Committed {
+                row: row.row,
+                recipient: row.recipient.clone(),
+                amount,
+                hash: receipt.hash.clone(),
+                version: receipt.version,
+            })?;
+            RowResult::Paid {
+                receipt,
+                created: report.created_recipient,
+            }
+        }
+        Outcome::Failed { error } => {
+            journal.record(Entry::Failed {
+                row: row.row,
+                recipient: row.recipient.clone(),
+                amount,
+                error: error.clone(),
+            })?;
+            RowResult::Failed { error }
+        }
+    })
+}
+
+// A row that failed before anything was signed, so nothing can reach the chain
+fn not_sent(
+    journal: &mut Journal,
+    row: &BatchRow,
+    amount: u64,
+    error: String,
+) -> Result<RowResult> {
+    journal.record(Entry::Failed {
+        row: row.row,
+        recipient: row.recipient.clone(),
+        amount,
+        error: error.clone(),
+    })?;
+    Ok(RowResult::Failed { error })
+}
+
+// Work out what became of the transaction signed with `sequence_number`, waiting until it
+// either committed or expired. An expired one can never commit, so the row is safe to send
+// again. This assumes nothing else sends from the same account while the batch runs
+async fn reconcile(
+    app: &App,
+    address: AccountAddress,
+    sequence_number: u64,
+    expiration_timestamp_secs: u64,
+) -> Result<Outcome> {
+    let retry = &app.config.retry;
+    loop {
+        let status = transaction::status(
+            &app.nodes,
+            retry,
+            address,
+            sequence_number,
+            expiration_timestamp_secs,
+        )
+        .await?;
+
+        match status {
+            Status::Committed => {
+                let transactions = app
+                    .nodes
+                    .run(retry, |client| async move {
+                        Ok(client
+                            .get_account_transactions(address, Some(sequence_number), Some(1))
+                            .await?)
+                    })
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Could not fetch transaction {} of {}",
+                            sequence_number,
+                            address.to_hex_literal()
+                        )
+                    })?
+                    .into_inner();
+                let transaction = transactions.first().with_context(|| {
+                    format!(
+                        "Node has no transaction {} for {}",
+                        sequence_number,
+                        address.to_hex_literal()
+                    )
+                })?;
+
+                return Ok(committed_outcome(Receipt::from_transaction(transaction)?));
+            }
+            Status::Expired => {
+                return Ok(Outcome::Failed {
+                    error: "transaction expired without being committed".to_string(),
+                });
+            }
+            Status::Pending => tokio::time::sleep(Duration::from_secs(1)).await,
+        }
+    }
+}
+
+// A committed transaction only paid the recipient if it also executed successfully
+fn committed_outcome(receipt: Receipt) -> Outcome {
+    match receipt.success {
+        true => Outcome::Committed { receipt },
+        false => Outcome::Failed {
+            error: format!("transaction {} failed: {}", receipt.hash, receipt.vm_status),
+        },
+    }
+}
+
+async fn resync_sequence_number(app: &App, sender: &mut Sender) -> Result<()> {
+    let address = sender.account.address();
+    *sender.account.sequence_number_mut() = app
+        .nodes
+        .run(&app.config.retry, |client| async move {
+            Ok(client.get_account(address).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch account {}", address.to_hex_literal()))?
+        .into_inner()
+        .sequence_number;
+    app.save_sender(sender)
+}
+
+fn print_report(rows: &[BatchRow], amounts: &[(u64, String)], results: &[RowResult]) {
+    println!("\n===== Batch report =====");
+    for ((row, (_, amount)), result) in rows.iter().zip(amounts).zip(results) {
+        let status = match result {
+            RowResult::Paid { receipt, created } => format!(
+                "{}paid in {} at version {}, fee {} octas",
+                match created {
+                    true => "account created, ",
+                    false => "",
+                },
+                receipt.hash,
+                receipt.version,
+                receipt.fee()
+            ),
+            RowResult::AlreadyPaid { hash } => format!("already paid in {}", hash),
+            RowResult::Failed { error } => format!("FAILED: {}", error),
+        };
+        println!(
+            "Row {}: {} to {}: {}",
+            row.row, amount, row.recipient, status
+        );
+    }
+
+    let paid = results
+        .iter()
+        .filter(|result| !matches!(result, RowResult::Failed { .. }))
+        .count();
+    println!("\n{} of {} rows paid", paid, rows.len());
+}
+
+// One transfer record per row, after a create record for recipients created on the way. Rows
+// paid in an earlier run only have the hash the journal kept, and failed rows have none but
+// say why they failed
+fn emit_records(
+    app: &App,
+    rows: &[BatchRow],
+    amounts: &[(u64, String)],
+    results: &[RowResult],
+) -> Result<()> {
+    for ((row, (amount, _)), result) in rows.iter().zip(amounts).zip(results) {
+        let address = app
+            .resolve_address(&row.recipient)
+            .map(|address| address.to_hex_literal())
+            .unwrap_or_default();
+        if let RowResult::Paid { created: true, .. } = result {
+            app.output.emit(Record::new(
+                RecordKind::Create,
+                &row.recipient,
+                address.clone(),
+                0,
+            ))?;
+        }
+        let mut record = Record::transfer(&row.recipient, address, *amount, None);
+        match result {
+            RowResult::Paid { receipt, .. } => {
+                record.tx_hash = Some(receipt.hash.clone());
+                record.version = Some(receipt.version);
+                record.fee = Some(receipt.fee());
+            }
+            RowResult::AlreadyPaid { hash } => record.tx_hash = Some(hash.clone()),
+            RowResult::Failed { error } => record.error = Some(error.clone()),
+        }
+        app.output.emit(record)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use anyhow::{anyhow, bail, Context, Result};
+use hdrhistogram::serialization::interval_log::IntervalLogWriterBuilder;
+use hdrhistogram::serialization::V2DeflateSerializer;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use super::App;
+use crate::amount::Denomination;
+use crate::bench::{Bench, BenchConfig, BenchReport, Load};
+use crate::cli::BenchArgs;
+use crate::output::OutputFormat;
+
+const DEFAULT_CONCURRENCY: usize = 8;
+
+// Percentiles shown for submit to commit latency
+const PERCENTILES: [f64; 6] = [50.0, 75.0, 90.0, 99.0, 99.9, 100.0];
+
+// The report in the json and ndjson formats. Latencies are in milliseconds
+#[derive(Debug, Serialize)]
+struct Summary {
+    mode: &'static str,
+    rate: Option<f64>,
+    concurrency: Option<usize>,
+    senders: usize,
+    elapsed_secs: f64,
+    submitted: u64,
+    committed: u64,
+    aborted: u64,
+    failed: u64,
+    skipped: u64,
+    submitted_tps: f64,
+    committed_tps: f64,
+    latency_ms: BTreeMap<String, f64>,
+    errors: BTreeMap<String, u64>,
+}
+
+pub async fn run(app: &App, args: BenchArgs) -> Result<()> {
+    if app.output.format() == OutputFormat::Csv {
+        bail!("bench reports a summary, use table, json or ndjson output");
+    }
+
+    let load = match (args.rate, args.concurrency) {
+        (Some(rate), _) => Load::Rate(rate),
+        (None, Some(0)) => bail!("--concurrency must be at least 1"),
+        (None, concurrency) => Load::Concurrency(concurrency.unwrap_or(DEFAULT_CONCURRENCY)),
+    };
+    let denomination = app.denomination(&app.config.coin_type).await?;
+    let config = BenchConfig {
+        load,
+        senders: args.senders,
+        fund_amount: Denomination::apt().parse(&args.fund)?,
+        amount: denomination.parse(&args.amount)?,
+        duration: Duration::from_secs(args.duration_secs),
+    };
+
+    let bench = Bench {
+        nodes: &app.nodes,
+        faucet_client: app.faucet_client()?,
+        options: app.transfer_options(),
+        wait_timeout: app.config.wait_timeout,
+        retry: app.config.retry.clone(),
+    };
+    if app.output.is_table() {
+        println!(
+            "Funding {} senders with {} each",
+            config.senders(),
+            Denomination::apt().format(config.fund_amount)
+        );
+    }
+    let started_at = SystemTime::now();
+    let report = bench.run(&config).await?;
+
+    if let Some(path) = &args.histogram_log {
+        write_histogram_log(path, &report, started_at)?;
+    }
+
+    match app.output.is_table() {
+        true => print_report(&report, &denomination),
+        false => app.output.summary(&summary(&report))?,
+    }
+
+    Ok(())
+}
+
+fn print_report(report: &BenchReport, denomination: &Denomination) {
+    println!("\n===== Bench =====");
+    match report.config.load {
+        Load::Rate(rate) => println!(
+            "Load: open loop at {} transfers/s from {} senders",
+            rate, report.config.senders
+        ),
+        Load::Concurrency(concurrency) => {
+            println!("Load: closed loop with {} transfers in flight", concurrency)
+        }
+    }
+    println!("Amount: {}", denomination.format(report.config.amount));
+    println!("Elapsed: {:.1}s", report.elapsed.as_secs_f64());
+    println!(
+        "Submitted: {} ({:.1} TPS)",
+        report.submitted,
+        report.submitted_tps()
+    );
+    println!(
+        "Committed: {} ({:.1} TPS)",
+        report.committed,
+        report.committed_tps()
+    );
+    println!("Aborted: {}", report.aborted);
+    println!("Failed: {}", report.failed);
+    if let Load::Rate(_) = report.config.load {
+        println!("Never fired, every sender busy: {}", report.skipped);
+    }
+
+    println!("\n===== Latency until commit =====");
+    match report.latency.is_empty() {
+        true => println!("No transfers committed"),
+        false => {
+            for percentile in PERCENTILES {
+                println!(
+                    "p{:<6} {:>10.1} ms",
+                    percentile,
+                    millis(report.latency.value_at_percentile(percentile))
+                );
+            }
+            println!("mean    {:>10.1} ms", report.latency.mean() / 1_000.0);
+        }
+    }
+
+    if !report.errors.is_empty() {
+        println!("\n===== Errors =====");
+        for (error, count) in &report.errors {
+            println!("{} x {}", count, error);
+        }
+    }
+}
+
+fn summary(report: &BenchReport) -> Summary {
+    let (mode, rate, concurrency) = match report.config.load {
+        Load::Rate(rate) => ("open", Some(rate), None),
+        Load::Concurrency(concurrency) => ("closed", None, Some(concurrency)),
+    };
+    let mut latency_ms = BTreeMap::new();
+    if !report.latency.is_empty() {
+        for percentile in PERCENTILES {
+            latency_ms.insert(
+                format!("p{}", percentile),
+                millis(report.latency.value_at_percentile(percentile)),
+            );
+        }
+        latency_ms.insert("mean".to_string(), report.latency.mean() / 1_000.0);
+    }
+
+    Summary {
+        mode,
+        rate,
+        concurrency,
+        senders: report.config.senders(),
+        elapsed_secs: report.elapsed.as_secs_f64(),
+        submitted: report.submitted,
+        committed: report.committed,
+        aborted: report.aborted,
+        failed: report.failed,
+        skipped: report.skipped,
+        submitted_tps: report.submitted_tps(),
+        committed_tps: report.committed_tps(),
+        latency_ms,
+        errors: report.errors.clone(),
+    }
+}
+
+// One interval covering the whole run, with values in microseconds shown as milliseconds by
+// HdrHistogram tools
+fn write_histogram_log(path: &Path, report: &BenchReport, started_at: SystemTime) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Could not create histogram log {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    let mut serializer = V2DeflateSerializer::new();
+    let context = || format!("Could not write histogram log {}", path.display());
+
+    let mut log = IntervalLogWriterBuilder::new()
+        .with_comment("Latency of committed transfers until commit, in microseconds")
+        .with_start_time(started_at)
+        .with_base_time(started_at)
+        .with_max_value_divisor(1_000.0)
+        .begin_log_with(&mut writer, &mut serializer)
+        .with_context(context)?;
+    log.write_histogram(&report.latency, Duration::ZERO, report.elapsed, None)
+        .map_err(|err| anyhow!("{:?}", err))
+        .with_context(context)?;
+    writer.flush().with_context(context)
+}
+
+fn millis(micros: u64) -> f64 {
+    micros as f64 / 1_000.0
+}
@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+
+use super::App;
+use crate::cli::BuildArgs;
+use crate::offline::TransactionFile;
+use crate::transaction;
+
+// Signing happens elsewhere, often on an offline machine, so unless --expiration-secs says
+// otherwise the transaction stays valid for the trip there and back
+const DEFAULT_EXPIRATION_SECS: u64 = 3600;
+
+// The only online step of offline signing: everything the signer cannot look up for itself
+// goes into the file
+pub async fn run(app: &App, args: BuildArgs) -> Result<()> {
+    let mut options = app.transfer_options();
+    if app.config.expiration_secs.is_none() {
+        options.timeout_secs = DEFAULT_EXPIRATION_SECS;
+    }
+    let denomination = app.denomination(options.coin_type).await?;
+    let amount = denomination.parse(&args.amount)?;
+    let sender = app.resolve_address(app.sender_name(args.from.as_deref())?)?;
+    let recipient = app.resolve_address(&args.to)?;
+
+    let sequence_number = app
+        .nodes
+        .run(&app.config.retry, |client| async move {
+            Ok(client.get_account(sender).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch account {}", sender.to_hex_literal()))?
+        .into_inner()
+        .sequence_number;
+    let chain_id = transaction::chain_id(&app.nodes, &app.config.retry).await?;
+
+    let raw = transaction::transfer_builder(
+        sender,
+        sequence_number,
+        recipient,
+        amount,
+        &options,
+        chain_id,
+    )?
+    .build();
+    let file = TransactionFile::unsigned(&raw)?;
+    file.write(&args.output_file)?;
+
+    println!("{}", file.summary);
+    println!(
+        "Wrote unsigned transaction to {}",
+        args.output_file.display()
+    );
+
+    Ok(())
+}
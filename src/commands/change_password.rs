@@ -0,0 +1,19 @@
+use anyhow::Result;
+
+use super::App;
+use crate::cli::ChangePasswordArgs;
+use crate::password::Password;
+
+pub fn run(app: &App, args: ChangePasswordArgs) -> Result<()> {
+    let account = app.load_keystore_account(&args.name)?;
+
+    let new_password = Password::new(args.new_password_file, "APTOS_KEYSTORE_NEW_PASSWORD");
+    app.keystore.set_private_key(
+        &args.name,
+        account.private_key(),
+        new_password.get_new("New password: ")?,
+    )?;
+
+    println!("Re-encrypted '{}' with the new password", args.name);
+    Ok(())
+}
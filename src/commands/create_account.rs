@@ -0,0 +1,35 @@
+use anyhow::{Context, Result};
+
+use super::App;
+use crate::cli::CreateAccountArgs;
+use crate::error::ClientError;
+use crate::output::{Record, RecordKind};
+
+pub async fn run(app: &App, args: CreateAccountArgs) -> Result<()> {
+    let address = app.resolve_address(&args.address)?;
+
+    let faucet_client = app.faucet_client()?;
+    app.config
+        .retry
+        .run(|| faucet_client.create_account(address))
+        .await
+        .map_err(ClientError::faucet)
+        .with_context(|| {
+            format!(
+                "Failed to create onchain account for {}",
+                address.to_hex_literal()
+            )
+        })?;
+
+    match app.output.is_table() {
+        true => println!("Created account {}", address.to_hex_literal()),
+        false => app.output.emit(Record::new(
+            RecordKind::Create,
+            &args.address,
+            address.to_hex_literal(),
+            0,
+        ))?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use anyhow::{bail, Result};
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use std::collections::HashMap;
+
+use super::{App, Sender};
+use crate::account;
+use crate::amount::Denomination;
+use crate::output::{Record, RecordKind};
+use crate::scenario::{Outcome, Scenario, Step, StepReport, TransferSettings};
+
+pub async fn run(app: &App) -> Result<()> {
+    let denomination = app.denomination(&app.config.coin_type).await?;
+
+    // Load alice and bob from the keystore, generating and saving them on the first run
+    let alice = load_or_generate(app, "alice").await?;
+    let bob = load_or_generate(app, "bob").await?;
+
+    if app.output.is_table() {
+        println!(
+            "\n===== Local Accounts ({}) =====",
+            app.config.network.profile
+        );
+        println!("Alice: {}", alice.account.address().to_hex_literal());
+        println!("Bob: {}", bob.account.address().to_hex_literal());
+    }
+
+    // Create and fund Alice's onchain account. Create Bob's onchain account. Accounts
+    // kept in the keystore from an earlier run already exist onchain and keep their coins
+    let mut steps = Vec::new();
+    let retry = &app.config.retry;
+    if account::get(&app.nodes, retry, alice.account.address())
+        .await?
+        .is_none()
+    {
+        steps.push(Step::Fund {
+            account: "alice".to_string(),
+            amount: 20_000,
+        });
+    }
+    if account::get(&app.nodes, retry, bob.account.address())
+        .await?
+        .is_none()
+    {
+        steps.push(Step::Create {
+            account: "bob".to_string(),
+        });
+    }
+
+    // Transfer 1000 coins from Alice to Bob twice, looking at the balances around each
+    let transfer = Step::Transfer {
+        from: "alice".to_string(),
+        to: "bob".to_string(),
+        amount: 1000,
+        settings: TransferSettings::default(),
+    };
+    for stage in ["Initial", "Intermediate", "Final"] {
+        if stage != "Initial" {
+            steps.push(transfer.clone());
+        }
+        steps.push(Step::Snapshot {
+            stage: stage.to_string(),
+        });
+    }
+
+    let scenario = Scenario {
+        name: "demo".to_string(),
+        accounts: vec!["alice".to_string(), "bob".to_string()],
+        steps,
+    };
+    let mut accounts = HashMap::from([
+        ("alice".to_string(), alice.account),
+        ("bob".to_string(), bob.account),
+    ]);
+    let report = app.runner().run(&scenario, &mut accounts).await?;
+
+    // Only alice sends, so hers is the only sequence number to write back
+    app.save_sender(&Sender {
+        name: alice.name,
+        account: accounts.remove("alice").unwrap(),
+    })?;
+
+    for step in &report.steps {
+        print_step(app, &report.accounts, &denomination, step)?;
+    }
+    // Every step is out before the first one that failed ends the demo
+    match report.steps.iter().find_map(|step| step.outcome.failure()) {
+        Some(failure) => bail!("{}", failure),
+        None => Ok(()),
+    }
+}
+
+// Show a step as the demo always has: a table per balance snapshot, in `denomination`, and a
+// receipt per transfer. The structured formats get a record for every step, failed ones
+// included. Also used by run-scenario
+pub(super) fn print_step(
+    app: &App,
+    addresses: &[(String, AccountAddress)],
+    denomination: &Denomination,
+    step: &StepReport,
+) -> Result<()> {
+    let address = |name: &str| {
+        addresses
+            .iter()
+            .find(|(account, _)| account == name)
+            .map(|(_, address)| address.to_hex_literal())
+            .unwrap_or_default()
+    };
+    let mut record = match (&step.step, &step.outcome) {
+        (Step::Transfer { to, amount, .. }, Outcome::Transferred { receipt, .. }) => {
+            if app.output.is_table() {
+                println!("\n===== Transfer receipt =====");
+            }
+            let mut record = Record::transfer(to, address(to), *amount, Some(receipt));
+            record.error = step.outcome.failure();
+            return app.output.transfer(record, receipt);
+        }
+        (Step::Snapshot { stage }, Outcome::Snapshot { balances }) => {
+            let stage_name = stage.to_lowercase();
+            return app.output.balances(
+                Some(&format!("{} balances", stage)),
+                denomination,
+                balances
+                    .iter()
+                    .map(|balance| {
+                        Record::balance(
+                            Some(&stage_name),
+                            &balance.account,
+                            balance.address.to_hex_literal(),
+                            balance.amount,
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        (Step::Transfer { to, amount, .. }, _) => Record::transfer(to, address(to), *amount, None),
+        (Step::Snapshot { stage }, _) => {
+            Record::balance(Some(&stage.to_lowercase()), "", String::new(), 0)
+        }
+        (Step::Fund { account, amount }, _) => {
+            Record::new(RecordKind::Fund, account, address(account), *amount)
+        }
+        (Step::Create { account }, _) => {
+            Record::new(RecordKind::Create, account, address(account), 0)
+        }
+        (Step::Wait { duration }, _) => Record::new(
+            RecordKind::Wait,
+            &format!("{}s", duration.as_secs()),
+            String::new(),
+            0,
+        ),
+        // The balance the account was found with, or the one it should have had if it could
+        // not be read
+        (
+            Step::AssertBalance {
+                account, amount, ..
+            },
+            outcome,
+        ) => {
+            let amount = match outcome {
+                Outcome::Balance { actual, .. } => *actual,
+                _ => *amount,
+            };
+            Record::new(RecordKind::Assertion, account, address(account), amount)
+        }
+    };
+    record.error = step.outcome.failure();
+
+    app.output.emit(record)
+}
+
+async fn load_or_generate(app: &App, name: &str) -> Result<Sender> {
+    if app.keystore.contains(name) {
+        return app.load_sender(name).await;
+    }
+
+    let account = LocalAccount::generate(&mut rand::rngs::OsRng);
+    let password = app.password.get_new("New password: ")?;
+    app.keystore.add(name, &account, password)?;
+
+    Ok(Sender {
+        name: Some(name.to_string()),
+        account,
+    })
+}
@@ -0,0 +1,104 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::types::transaction::SignedTransaction;
+use std::fs;
+use std::path::Path;
+
+use super::App;
+use crate::api_json;
+use crate::cli::ExplainArgs;
+use crate::error::ClientError;
+use crate::offline::TransactionFile;
+use crate::receipt::Receipt;
+use crate::summary::Summary;
+use crate::transaction;
+
+pub async fn run(app: &App, args: ExplainArgs) -> Result<()> {
+    let path = Path::new(&args.transaction);
+    match path.exists() {
+        true => explain_file(path),
+        false => explain_hash(app, &args.transaction).await,
+    }
+}
+
+// Transaction files from `build` and `sign` are taken as they are, anything else has to be a
+// signed transaction in BCS, binary or hex encoded
+fn explain_file(path: &Path) -> Result<()> {
+    let contents = fs::read(path).with_context(|| format!("Could not read {}", path.display()))?;
+    if let Ok(file) = serde_json::from_slice::<TransactionFile>(&contents) {
+        if file.signed_transaction.is_some() {
+            return explain_signed(&file.signed_transaction()?);
+        }
+        file.raw_transaction()?;
+        println!("{}", file.summary);
+        println!("Signature: none, the transaction has not been signed");
+        return Ok(());
+    }
+
+    let decoded = std::str::from_utf8(&contents)
+        .ok()
+        .and_then(|text| hex::decode(text.trim().trim_start_matches("0x")).ok());
+    let signed: SignedTransaction = bcs::from_bytes(decoded.as_deref().unwrap_or(&contents[..]))
+        .with_context(|| {
+            format!(
+                "{} is neither a transaction file nor a signed transaction in BCS",
+                path.display()
+            )
+        })?;
+    explain_signed(&signed)
+}
+
+fn explain_signed(signed: &SignedTransaction) -> Result<()> {
+    println!("{}", Summary::of(&signed.clone().into_raw_transaction())?);
+    println!("Hash: {}", signed.clone().committed_hash().to_hex_literal());
+    match signed.clone().check_signature() {
+        Ok(_) => println!("Signature: valid"),
+        Err(err) => println!("Signature: INVALID ({})", err),
+    }
+
+    Ok(())
+}
+
+// A transaction the node has, pending or committed. Committed ones also get their outcome
+async fn explain_hash(app: &App, hash: &str) -> Result<()> {
+    let hash_value = HashValue::from_hex(hash.trim_start_matches("0x")).with_context(|| {
+        format!(
+            "'{}' is neither a transaction file nor a transaction hash",
+            hash
+        )
+    })?;
+    let transaction = app
+        .nodes
+        .run(&app.config.retry, |client| async move {
+            Ok(client.get_transaction_by_hash(hash_value).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch transaction {}", hash))?
+        .into_inner();
+    let chain_id = transaction::chain_id(&app.nodes, &app.config.retry).await?;
+
+    let json = api_json::transaction(&transaction)?;
+    let kind = json["type"]
+        .as_str()
+        .unwrap_or("transaction of unknown type");
+    if kind != "user_transaction" && kind != "pending_transaction" {
+        bail!("{} is a {}, not a user transaction", hash, kind);
+    }
+
+    println!("{}", Summary::from_json(&json, chain_id)?);
+    if kind == "pending_transaction" {
+        println!("Status: pending");
+        return Ok(());
+    }
+    let receipt = Receipt::from_transaction(&transaction)?;
+    println!();
+    receipt.print();
+    if !receipt.success {
+        println!(
+            "Reason: {}",
+            ClientError::from_vm_status(&receipt.vm_status)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::{Context, Result};
+
+use super::App;
+use crate::cli::ExportArgs;
+use crate::keyfile::{EncryptedKey, KeyFile};
+use crate::keystore::write_secret;
+
+pub fn run(app: &App, args: ExportArgs) -> Result<()> {
+    let stored = app.keystore.get(&args.name)?;
+    let address = stored.address()?;
+
+    let contents = if args.plaintext {
+        let account = app.load_keystore_account(&args.name)?;
+        format!("0x{}", hex::encode(account.private_key().to_bytes()))
+    } else {
+        // Encrypted keys are exported as they are; plaintext ones are encrypted on the way out
+        let encrypted_key = match stored.encrypted_key {
+            Some(encrypted_key) => encrypted_key,
+            None => {
+                let private_key = stored.private_key(None)?;
+                let password = app.password.get_new("New password: ")?;
+                EncryptedKey::encrypt(&private_key, address, password)?
+            }
+        };
+        let key_file = KeyFile {
+            address: address.to_hex_literal(),
+            encrypted_key,
+        };
+        serde_json::to_string_pretty(&key_file)?
+    };
+
+    match args.output_file {
+        Some(path) => {
+            write_secret(&path, contents.as_bytes())
+                .with_context(|| format!("Could not write key file {}", path.display()))?;
+            println!("Exported '{}' to {}", args.name, path.display());
+        }
+        None => println!("{}", contents),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+
+use super::App;
+use crate::amount::Denomination;
+use crate::cli::FundArgs;
+use crate::error::ClientError;
+use crate::output::{Record, RecordKind};
+
+pub async fn run(app: &App, args: FundArgs) -> Result<()> {
+    let address = app.resolve_address(&args.address)?;
+    let denomination = Denomination::apt();
+    let amount = denomination.parse(&args.amount)?;
+
+    // Retrying may mint twice if an attempt went through unseen, which a faucet allows
+    let faucet_client = app.faucet_client()?;
+    app.config
+        .retry
+        .run(|| faucet_client.fund(address, amount))
+        .await
+        .map_err(ClientError::faucet)
+        .with_context(|| format!("Failed to fund {}", address.to_hex_literal()))?;
+
+    match app.output.is_table() {
+        true => println!(
+            "Funded {} with {}",
+            address.to_hex_literal(),
+            denomination.format(amount)
+        ),
+        false => app.output.emit(Record::new(
+            RecordKind::Fund,
+            &args.address,
+            address.to_hex_literal(),
+            amount,
+        ))?,
+    }
+
+    Ok(())
+}
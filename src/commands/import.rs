@@ -0,0 +1,55 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::crypto::PrivateKey;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+use aptos_sdk::types::LocalAccount;
+use std::fs;
+use std::str::FromStr;
+
+use super::App;
+use crate::cli::ImportArgs;
+use crate::keyfile::{parse_private_key, KeyFile};
+
+pub fn run(app: &App, args: ImportArgs) -> Result<()> {
+    if let Some(path) = &args.key_file {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read key file {}", path.display()))?;
+        let key_file: KeyFile = serde_json::from_str(&contents)
+            .with_context(|| format!("Could not parse key file {}", path.display()))?;
+        let address = AccountAddress::from_str(&key_file.address)
+            .context("Key file has an invalid address")?;
+
+        // Make sure the key file decrypts before it goes into the keystore
+        key_file
+            .encrypted_key
+            .decrypt(address, app.password.get("Key file password: ")?)?;
+        app.keystore
+            .add_encrypted(&args.name, address, key_file.encrypted_key)?;
+
+        println!("Imported '{}' ({})", args.name, address.to_hex_literal());
+        return Ok(());
+    }
+
+    let encoded = match &args.private_key_file {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Could not read private key file {}", path.display()))?,
+        None => {
+            rpassword::prompt_password("Private key: ").context("Could not read private key")?
+        }
+    };
+    if encoded.trim().is_empty() {
+        bail!("Private key must not be empty");
+    }
+
+    let private_key = parse_private_key(&encoded)?;
+    let address = AuthenticationKey::ed25519(&private_key.public_key()).derived_address();
+    let account = LocalAccount::new(address, private_key, 0);
+    app.keystore.add(
+        &args.name,
+        &account,
+        app.password.get_new("New password: ")?,
+    )?;
+
+    println!("Imported '{}' ({})", args.name, address.to_hex_literal());
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use aptos_sdk::types::LocalAccount;
+
+use super::App;
+use crate::cli::KeygenArgs;
+use crate::mnemonic;
+
+pub fn run(app: &App, args: KeygenArgs) -> Result<()> {
+    let account = if args.mnemonic {
+        let phrase = mnemonic::read_phrase(args.mnemonic_file.as_deref())?;
+        println!("Derivation path: {}", mnemonic::derivation_path(args.index));
+        mnemonic::derive_account(&phrase, args.index)?
+    } else {
+        LocalAccount::generate(&mut rand::rngs::OsRng)
+    };
+
+    println!("Address: {}", account.address().to_hex_literal());
+    println!(
+        "Public key: 0x{}",
+        hex::encode(account.public_key().to_bytes())
+    );
+
+    match args.name {
+        Some(name) => {
+            let password = app.password.get_new("New password: ")?;
+            app.keystore.add(&name, &account, password)?;
+            println!("Saved as '{}' in {}", name, app.keystore.dir().display());
+        }
+        None => println!(
+            "Private key: 0x{}",
+            hex::encode(account.private_key().to_bytes())
+        ),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::crypto::ed25519::Ed25519PrivateKey;
+use aptos_sdk::crypto::PrivateKey;
+use aptos_sdk::rest_client::{FaucetClient, PendingTransaction};
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+use aptos_sdk::types::LocalAccount;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::account;
+use crate::amount::Denomination;
+use crate::cli::{Cli, Command};
+use crate::coin;
+use crate::config::Config;
+use crate::keyfile::parse_private_key;
+use crate::keystore::Keystore;
+use crate::output::Output;
+use crate::password::Password;
+use crate::pool::NodePool;
+use crate::receipt::{self, Receipt};
+use crate::retry::RetryPolicy;
+use crate::scenario::Runner;
+
+mod accounts;
+mod balance;
+mod batch;
+mod bench;
+mod build;
+mod change_password;
+mod create_account;
+mod demo;
+mod explain;
+mod export;
+mod fund;
+mod import;
+mod keygen;
+mod run_scenario;
+mod scan;
+mod sign;
+mod submit;
+mod transfer;
+
+// State shared by every subcommand. Clients for the node are handed out by the pool, so they
+// always talk to whichever node is healthy at the time
+pub struct App {
+    pub config: Config,
+    pub nodes: NodePool,
+    pub keystore: Keystore,
+    pub password: Password,
+    pub output: Output,
+}
+
+// A sending account along with the keystore name it was loaded from, if any, so the
+// sequence number can be written back after transfers
+pub struct Sender {
+    pub name: Option<String>,
+    pub account: LocalAccount,
+}
+
+impl App {
+    fn new(cli: &Cli, config: Config) -> Result<Self> {
+        let keystore_dir = match &cli.keystore {
+            Some(dir) => dir.clone(),
+            None => Keystore::default_dir()?,
+        };
+
+        Ok(Self {
+            nodes: config.network.node_pool(),
+            keystore: Keystore::open(keystore_dir)?,
+            password: Password::new(cli.password_file.clone(), "APTOS_KEYSTORE_PASSWORD"),
+            output: Output::new(config.output),
+            config,
+        })
+    }
+
+    // Mainnet and custom networks without --faucet-url have no faucet to call
+    pub fn faucet_client(&self) -> Result<FaucetClient> {
+        match &self.config.network.faucet_url {
+            Some(faucet_url) => Ok(self.nodes.faucet_client(faucet_url)),
+            None => bail!(
+                "The {} network has no faucet, set faucet_url",
+                self.config.network.profile
+            ),
+        }
+    }
+
+    // The sender named on the command line, or the configured default_sender
+    pub fn sender_name<'a>(&'a self, from: Option<&'a str>) -> Result<&'a str> {
+        match from.or(self.config.default_sender.as_deref()) {
+            Some(name) => Ok(name),
+            None => bail!("No sender given, pass --from or set default_sender"),
+        }
+    }
+
+    // Options for every transfer we send: the configured coin type, with configured gas and
+    // expiration settings replacing the SDK's defaults
+    pub fn transfer_options(&self) -> TransferOptions<'_> {
+        let mut options = TransferOptions {
+            coin_type: &self.config.coin_type,
+            ..TransferOptions::default()
+        };
+        if let Some(max_gas_amount) = self.config.gas.max_gas_amount {
+            options.max_gas_amount = max_gas_amount;
+        }
+        if let Some(gas_unit_price) = self.config.gas.gas_unit_price {
+            options.gas_unit_price = gas_unit_price;
+        }
+        if let Some(expiration_secs) = self.config.expiration_secs {
+            options.timeout_secs = expiration_secs;
+        }
+
+        options
+    }
+
+    // A scenario runner using this app's nodes, faucet and transfer options
+    pub fn runner(&self) -> Runner<'_> {
+        Runner {
+            nodes: &self.nodes,
+            faucet_client: self.faucet_client().ok(),
+            options: self.transfer_options(),
+            wait_timeout: self.config.wait_timeout,
+            retry: self.config.retry.clone(),
+        }
+    }
+
+    // How amounts of a coin are parsed and shown, with the configured decimals if any
+    pub async fn denomination(&self, coin_type: &str) -> Result<Denomination> {
+        let mut denomination =
+            coin::denomination(&self.nodes, &self.config.retry, coin_type).await?;
+        if let Some(decimals) = self.config.decimals {
+            denomination.decimals = decimals;
+        }
+
+        Ok(denomination)
+    }
+
+    // Wait for a submitted transaction, giving up after the configured timeout, and read
+    // its receipt
+    pub async fn wait_for_transaction(&self, pending: &PendingTransaction) -> Result<Receipt> {
+        receipt::wait(
+            &self.nodes,
+            &self.config.retry,
+            pending,
+            self.config.wait_timeout,
+        )
+        .await
+    }
+
+    // Resolve a keystore account name or a literal address
+    pub fn resolve_address(&self, name_or_address: &str) -> Result<AccountAddress> {
+        if self.keystore.contains(name_or_address) {
+            return self.keystore.get(name_or_address)?.address();
+        }
+
+        AccountAddress::from_str(name_or_address).with_context(|| {
+            format!(
+                "'{}' is neither a keystore account nor an address",
+                name_or_address
+            )
+        })
+    }
+
+    // Load a sender from the keystore by name, or from a literal private key
+    pub async fn load_sender(&self, name_or_key: &str) -> Result<Sender> {
+        if self.keystore.contains(name_or_key) {
+            let mut account = self.load_keystore_account(name_or_key)?;
+            self.sync_sequence_number(&mut account).await;
+
+            return Ok(Sender {
+                name: Some(name_or_key.to_string()),
+                account,
+            });
+        }
+
+        let private_key = parse_private_key(name_or_key)
+            .context("Sender is neither a keystore account nor a private key")?;
+        Ok(Sender {
+            name: None,
+            account: load_account(&self.nodes, &self.config.retry, private_key).await?,
+        })
+    }
+
+    // Load and decrypt a keystore account, asking for the password only if it is encrypted
+    pub fn load_keystore_account(&self, name: &str) -> Result<LocalAccount> {
+        let stored = self.keystore.get(name)?;
+        let password = match stored.is_encrypted() {
+            true => Some(self.password.get(&format!("Password for '{}': ", name))?),
+            false => None,
+        };
+
+        stored.into_account(password)
+    }
+
+    // Write the sender's sequence number back to the keystore if it came from there
+    pub fn save_sender(&self, sender: &Sender) -> Result<()> {
+        match &sender.name {
+            Some(name) => self.keystore.save_sequence_number(name, &sender.account),
+            None => Ok(()),
+        }
+    }
+
+    // The stored sequence number is only the last one we knew about, so move forward to
+    // the onchain value if the account was used elsewhere in the meantime
+    pub async fn sync_sequence_number(&self, account: &mut LocalAccount) {
+        let onchain = account::get(&self.nodes, &self.config.retry, account.address()).await;
+        if let Ok(Some(onchain)) = onchain {
+            if onchain.sequence_number > account.sequence_number() {
+                *account.sequence_number_mut() = onchain.sequence_number;
+            }
+        }
+    }
+}
+
+// Run the command with `config`, which the caller loads from `cli` so it can classify errors
+// under the same retry settings
+pub async fn run(cli: Cli, config: Config) -> Result<()> {
+    let app = App::new(&cli, config)?;
+    if let Some(name) = text_only(&cli.command) {
+        if !app.output.is_table() {
+            bail!(
+                "`{}` only prints text, so it has no {} output",
+                name,
+                app.output.format()
+            );
+        }
+    }
+
+    // Keystore management works offline, everything else talks to the nodes and must be
+    // sure they are healthy and on the intended network before touching any account
+    if uses_network(&cli.command) {
+        for (url, health) in app.config.network.check_nodes(&app.nodes).await? {
+            eprintln!("Warning: skipping node {}: {}", url, health);
+        }
+    }
+
+    let result = match cli.command {
+        Command::Transfer(args) => transfer::run(&app, args).await,
+        Command::Batch(args) => batch::run(&app, args).await,
+        Command::Build(args) => build::run(&app, args).await,
+        Command::Sign(args) => sign::run(&app, args),
+        Command::Submit(args) => submit::run(&app, args).await,
+        Command::Explain(args) => explain::run(&app, args).await,
+        Command::Balance(args) => balance::run(&app, args).await,
+        Command::Fund(args) => fund::run(&app, args).await,
+        Command::CreateAccount(args) => create_account::run(&app, args).await,
+        Command::Keygen(args) => keygen::run(&app, args),
+        Command::Accounts => accounts::run(&app),
+        Command::Scan(args) => scan::run(&app, args).await,
+        Command::Import(args) => import::run(&app, args),
+        Command::Export(args) => export::run(&app, args),
+        Command::ChangePassword(args) => change_password::run(&app, args),
+        Command::Demo => demo::run(&app).await,
+        Command::RunScenario(args) => run_scenario::run(&app, args).await,
+        Command::Bench(args) => bench::run(&app, args).await,
+    };
+
+    // Records written before a failure are still finished, so JSON output stays a document
+    let finished = app.output.finish();
+    result.and(finished)
+}
+
+// Commands whose output is for people only, with nothing to put in records
+fn text_only(command: &Command) -> Option<&'static str> {
+    match command {
+        Command::Build(_) => Some("build"),
+        Command::Sign(_) => Some("sign"),
+        Command::Explain(_) => Some("explain"),
+        Command::Keygen(_) => Some("keygen"),
+        Command::Accounts => Some("accounts"),
+        Command::Scan(_) => Some("scan"),
+        Command::Import(_) => Some("import"),
+        Command::Export(_) => Some("export"),
+        Command::ChangePassword(_) => Some("change-password"),
+        _ => None,
+    }
+}
+
+// Explaining a file is done offline, only a hash needs the node
+fn uses_network(command: &Command) -> bool {
+    if let Command::Explain(args) = command {
+        return !Path::new(&args.transaction).exists();
+    }
+
+    !matches!(
+        command,
+        Command::Keygen(_)
+            | Command::Sign(_)
+            | Command::Accounts
+            | Command::Import(_)
+            | Command::Export(_)
+            | Command::ChangePassword(_)
+    )
+}
+
+// Build a LocalAccount for a private key, picking up its current sequence number from chain
+pub async fn load_account(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    private_key: Ed25519PrivateKey,
+) -> Result<LocalAccount> {
+    let address = AuthenticationKey::ed25519(&private_key.public_key()).derived_address();
+    let sequence_number = nodes
+        .run(retry, |client| async move {
+            Ok(client.get_account(address).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch account {}", address.to_hex_literal()))?
+        .into_inner()
+        .sequence_number;
+
+    Ok(LocalAccount::new(address, private_key, sequence_number))
+}
@@ -0,0 +1,146 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+use super::{demo, App};
+use crate::amount::Denomination;
+use crate::cli::RunScenarioArgs;
+use crate::scenario::{Outcome, Report, Step};
+use crate::scenario_file::ScenarioFile;
+
+pub async fn run(app: &App, args: RunScenarioArgs) -> Result<()> {
+    let file = ScenarioFile::load(&args.file)?;
+    let default_coin = app.config.coin_type.as_str();
+
+    let mut denominations = HashMap::new();
+    for coin_type in file.coin_types(default_coin) {
+        let denomination = app.denomination(&coin_type).await?;
+        denominations.insert(coin_type, denomination);
+    }
+    let scenario = file.resolve(&args.file, &denominations, default_coin)?;
+
+    let report = app.runner().run(&scenario, &mut HashMap::new()).await?;
+    match app.output.is_table() {
+        true => print_report(&report, &denominations, default_coin),
+        false => {
+            let denomination = denominations
+                .get(default_coin)
+                .cloned()
+                .unwrap_or_else(Denomination::apt);
+            for step in &report.steps {
+                demo::print_step(app, &report.accounts, &denomination, step)?;
+            }
+        }
+    }
+
+    if !report.passed(&scenario) {
+        bail!("Scenario '{}' failed", scenario.name);
+    }
+
+    Ok(())
+}
+
+fn print_report(
+    report: &Report,
+    denominations: &HashMap<String, Denomination>,
+    default_coin: &str,
+) {
+    let denomination = |coin_type: &Option<String>| match coin_type {
+        Some(coin_type) => denominations[coin_type].clone(),
+        None => denominations
+            .get(default_coin)
+            .cloned()
+            .unwrap_or_else(Denomination::apt),
+    };
+    let apt = Denomination::apt();
+
+    println!("\n===== Scenario {} =====", report.scenario);
+    for (name, address) in &report.accounts {
+        println!("{}: {}", name, address.to_hex_literal());
+    }
+
+    for (index, step) in report.steps.iter().enumerate() {
+        let description = match &step.step {
+            Step::Fund { account, amount } => {
+                format!("fund {} with {}", account, apt.format(*amount))
+            }
+            Step::Create { account } => format!("create {}", account),
+            Step::Transfer {
+                from,
+                to,
+                amount,
+                settings,
+            } => format!(
+                "transfer {} from {} to {}",
+                denomination(&settings.coin_type).format(*amount),
+                from,
+                to
+            ),
+            Step::Wait { duration } => format!("wait {}s", duration.as_secs()),
+            Step::AssertBalance {
+                account,
+                amount,
+                tolerance,
+                coin_type,
+            } => {
+                let denomination = denomination(coin_type);
+                match tolerance {
+                    0 => format!("{} has {}", account, denomination.format(*amount)),
+                    _ => format!(
+                        "{} has {} give or take {}",
+                        account,
+                        denomination.format(*amount),
+                        denomination.format(*tolerance)
+                    ),
+                }
+            }
+            Step::Snapshot { stage } => format!("{} balances", stage),
+        };
+
+        let result = match (&step.step, &step.outcome) {
+            (_, Outcome::Failed { error }) => format!("FAILED: {}", error),
+            (
+                _,
+                Outcome::Transferred {
+                    receipt,
+                    conservation,
+                },
+            ) => match (receipt.success, conservation.holds()) {
+                (true, true) => format!(
+                    "ok in {} at version {}, fee {}",
+                    receipt.hash,
+                    receipt.version,
+                    apt.format(receipt.fee())
+                ),
+                (false, _) => format!("FAILED in {}: {}", receipt.hash, receipt.vm_status),
+                (true, false) => format!("FAILED in {}: {}", receipt.hash, conservation),
+            },
+            (Step::AssertBalance { coin_type, .. }, Outcome::Balance { actual, .. }) => {
+                match step.outcome.passed() {
+                    true => "ok".to_string(),
+                    false => format!("FAILED, actual {}", denomination(coin_type).format(*actual)),
+                }
+            }
+            (_, Outcome::Snapshot { balances }) => balances
+                .iter()
+                .map(|balance| {
+                    format!(
+                        "{} {}",
+                        balance.account,
+                        denomination(&None).format(balance.amount)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => "ok".to_string(),
+        };
+
+        println!("Step {}: {}: {}", index + 1, description, result);
+    }
+
+    let passed = report
+        .steps
+        .iter()
+        .filter(|step| step.outcome.passed())
+        .count();
+    println!("{} of {} steps passed", passed, report.steps.len());
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use aptos_sdk::coin_client::CoinClient;
+
+use super::App;
+use crate::account;
+use crate::amount::Denomination;
+use crate::cli::ScanArgs;
+use crate::mnemonic;
+
+pub async fn run(app: &App, args: ScanArgs) -> Result<()> {
+    let phrase = mnemonic::read_phrase(args.mnemonic_file.as_deref())?;
+
+    let mut used = 0;
+    for index in args.start..args.start.saturating_add(args.count) {
+        let address = mnemonic::derive_account(&phrase, index)?.address();
+
+        match account::get(&app.nodes, &app.config.retry, address).await? {
+            Some(account) => {
+                let balance = app
+                    .nodes
+                    .run(&app.config.retry, |client| async move {
+                        CoinClient::new(&client).get_account_balance(&address).await
+                    })
+                    .await
+                    .with_context(|| {
+                        format!("Could not fetch balance of {}", address.to_hex_literal())
+                    })?;
+                used += 1;
+                println!(
+                    "{} {}: sequence number {}, balance {}",
+                    mnemonic::derivation_path(index),
+                    address.to_hex_literal(),
+                    account.sequence_number,
+                    Denomination::apt().format(balance)
+                );
+            }
+            None => println!(
+                "{} {}: no onchain account",
+                mnemonic::derivation_path(index),
+                address.to_hex_literal()
+            ),
+        }
+    }
+
+    println!(
+        "\n{} of {} derived addresses have onchain accounts",
+        used, args.count
+    );
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::{bail, Result};
+use aptos_sdk::crypto::PrivateKey;
+use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+use aptos_sdk::types::LocalAccount;
+
+use super::App;
+use crate::cli::SignArgs;
+use crate::keyfile::parse_private_key;
+use crate::offline::TransactionFile;
+
+// Runs entirely offline: the key comes from the keystore or the command line, and the
+// sequence number and chain id were fixed by `build`
+pub fn run(app: &App, args: SignArgs) -> Result<()> {
+    let raw = TransactionFile::read(&args.file)?.raw_transaction()?;
+
+    let account = match app.keystore.contains(&args.key) {
+        true => app.load_keystore_account(&args.key)?,
+        false => {
+            let private_key = parse_private_key(&args.key)?;
+            let address = AuthenticationKey::ed25519(&private_key.public_key()).derived_address();
+            LocalAccount::new(address, private_key, 0)
+        }
+    };
+    if account.address() != raw.sender() {
+        bail!(
+            "The key is for {} but the transaction is sent from {}",
+            account.address().to_hex_literal(),
+            raw.sender().to_hex_literal()
+        );
+    }
+
+    let signed = raw
+        .sign(account.private_key(), account.public_key().clone())?
+        .into_inner();
+    let file = TransactionFile::signed(&signed)?;
+    file.write(&args.output_file)?;
+
+    println!("{}", file.summary);
+    println!("Wrote signed transaction to {}", args.output_file.display());
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::{Context, Result};
+
+use super::App;
+use crate::cli::SubmitArgs;
+use crate::error::ClientError;
+use crate::offline::TransactionFile;
+use crate::output::Record;
+use crate::summary::CoinTransfer;
+use crate::transaction;
+
+pub async fn run(app: &App, args: SubmitArgs) -> Result<()> {
+    let file = TransactionFile::read(&args.file)?;
+    let signed = file.signed_transaction()?;
+    if app.output.is_table() {
+        println!("{}\n", file.summary);
+    }
+
+    let pending = transaction::submit(&app.nodes, &app.config.retry, &signed)
+        .await
+        .context("Failed to submit signed transaction")?;
+    let receipt = app.wait_for_transaction(&pending).await?;
+    match CoinTransfer::decode(signed.payload()) {
+        Some(transfer) => app.output.transfer(
+            Record::transfer(
+                &transfer.recipient.to_hex_literal(),
+                transfer.recipient.to_hex_literal(),
+                transfer.amount,
+                Some(&receipt),
+            ),
+            &receipt,
+        )?,
+        None => app.output.transfer(
+            Record::transaction(
+                &file.summary.function,
+                file.summary.sender.clone(),
+                &receipt,
+            ),
+            &receipt,
+        )?,
+    }
+
+    if !receipt.success {
+        return Err(ClientError::from_vm_status(&receipt.vm_status))
+            .with_context(|| format!("Transaction {} failed", receipt.hash));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::types::account_address::AccountAddress;
+
+use super::{App, Sender};
+use crate::amount::Denomination;
+use crate::cli::TransferArgs;
+use crate::coin;
+use crate::conservation::{Balances, Conservation};
+use crate::error::ClientError;
+use crate::output::{Record, RecordKind};
+use crate::preflight;
+use crate::transaction::{self, Simulation};
+
+pub async fn run(app: &App, args: TransferArgs) -> Result<()> {
+    let coin_type = &app.config.coin_type;
+    let denomination = app.denomination(coin_type).await?;
+    let amount = denomination.parse(&args.amount)?;
+
+    let mut sender = app
+        .load_sender(app.sender_name(args.from.as_deref())?)
+        .await?;
+    let recipient = app.resolve_address(&args.to)?;
+
+    if args.simulate {
+        return simulate(app, &denomination, &mut sender, &args.to, recipient, amount).await;
+    }
+
+    // Catch what would make the transfer fail before it uses up a sequence number
+    let faucet_client = match args.auto_create {
+        true => Some(app.faucet_client()?),
+        false => None,
+    };
+    let report = preflight::check(
+        &app.nodes,
+        &app.config.retry,
+        sender.account.address(),
+        recipient,
+        amount,
+        &app.transfer_options(),
+        faucet_client.as_ref(),
+    )
+    .await?;
+    if report.created_recipient {
+        match app.output.is_table() {
+            true => println!("Created recipient account {}", recipient.to_hex_literal()),
+            false => app.output.emit(Record::new(
+                RecordKind::Create,
+                &args.to,
+                recipient.to_hex_literal(),
+                0,
+            ))?,
+        }
+    }
+
+    let before = Balances::fetch(
+        &app.nodes,
+        &app.config.retry,
+        sender.account.address(),
+        recipient,
+        coin_type,
+    )
+    .await?;
+    let tx_hash = transaction::transfer(
+        &app.nodes,
+        &app.config.retry,
+        &mut sender.account,
+        recipient,
+        amount,
+        &app.transfer_options(),
+    )
+    .await
+    .context("Failed to transfer coins")?;
+    app.save_sender(&sender)?;
+
+    let receipt = app.wait_for_transaction(&tx_hash).await?;
+    app.output.transfer(
+        Record::transfer(&args.to, recipient.to_hex_literal(), amount, Some(&receipt)),
+        &receipt,
+    )?;
+
+    let after = Balances::fetch(
+        &app.nodes,
+        &app.config.retry,
+        sender.account.address(),
+        recipient,
+        coin_type,
+    )
+    .await?;
+    app.output.balances(
+        Some("Balances after transfer"),
+        &denomination,
+        vec![
+            Record::balance(
+                Some("after"),
+                sender.name.as_deref().unwrap_or("sender"),
+                sender.account.address().to_hex_literal(),
+                after.sender,
+            ),
+            Record::balance(
+                Some("after"),
+                &args.to,
+                recipient.to_hex_literal(),
+                after.receiver,
+            ),
+        ],
+    )?;
+
+    // Gas is the only thing a transfer should cost on top of the amount sent
+    let conservation = Conservation::new(
+        before,
+        after,
+        amount,
+        &receipt,
+        sender.account.address(),
+        recipient,
+        coin_type,
+    );
+    if !conservation.holds() {
+        bail!("Transfer {} left {}", receipt.hash, conservation);
+    }
+    // Committed, but the VM refused it, and all the sender paid for was gas
+    if !receipt.success {
+        return Err(ClientError::from_vm_status(&receipt.vm_status))
+            .with_context(|| format!("Transfer {} failed", receipt.hash));
+    }
+
+    Ok(())
+}
+
+// Sign the transfer exactly as it would be sent, but only ask the node what would happen.
+// The sender's sequence number is not saved, so nothing is consumed
+async fn simulate(
+    app: &App,
+    denomination: &Denomination,
+    sender: &mut Sender,
+    label: &str,
+    recipient: AccountAddress,
+    amount: u64,
+) -> Result<()> {
+    let options = app.transfer_options();
+    let chain_id = transaction::chain_id(&app.nodes, &app.config.retry).await?;
+    let builder = transaction::transfer_builder(
+        sender.account.address(),
+        sender.account.sequence_number(),
+        recipient,
+        amount,
+        &options,
+        chain_id,
+    )?;
+    let signed = sender.account.sign_with_transaction_builder(builder);
+
+    let simulation =
+        transaction::simulate(&app.nodes, &app.config.retry, &signed, options.coin_type).await?;
+
+    // Each CoinStore the transfer would write, with its balance now and after
+    let mut changes = Vec::new();
+    for (address, after) in &simulation.coin_writes {
+        let before = coin::balance(&app.nodes, &app.config.retry, *address, options.coin_type)
+            .await?
+            .unwrap_or(0);
+        changes.push((address.to_hex_literal(), before, *after));
+    }
+
+    match app.output.is_table() {
+        true => print_simulation(denomination, &options, amount, &simulation, &changes),
+        false => {
+            app.output.emit(Record::simulation(
+                label,
+                recipient.to_hex_literal(),
+                amount,
+                simulation.fee(),
+            ))?;
+            for (address, before, after) in changes {
+                app.output.emit(Record::balance(
+                    Some("before"),
+                    &address,
+                    address.clone(),
+                    before,
+                ))?;
+                app.output.emit(Record::balance(
+                    Some("simulated"),
+                    &address,
+                    address.clone(),
+                    after,
+                ))?;
+            }
+        }
+    }
+
+    // A simulation that predicts failure fails the command the way the transfer would have
+    if !simulation.success {
+        return Err(ClientError::from_vm_status(&simulation.vm_status))
+            .context("Simulated transfer would fail");
+    }
+
+    Ok(())
+}
+
+fn print_simulation(
+    denomination: &Denomination,
+    options: &TransferOptions,
+    amount: u64,
+    simulation: &Simulation,
+    changes: &[(String, u64, u64)],
+) {
+    println!(
+        "Simulated transfer of {} ({})",
+        denomination.format(amount),
+        options.coin_type
+    );
+    let outcome = match simulation.success {
+        true => "success",
+        false => "failure",
+    };
+    println!("VM status: {} ({})", simulation.vm_status, outcome);
+    println!("Gas used: {}", simulation.gas_used);
+    println!(
+        "Fee: {} ({} gas x {} octas per unit)",
+        Denomination::apt().format(simulation.fee()),
+        simulation.gas_used,
+        simulation.gas_unit_price
+    );
+
+    println!("\n===== Balance changes =====");
+    for (address, before, after) in changes {
+        let (sign, change) = match after >= before {
+            true => ('+', after - before),
+            false => ('-', before - after),
+        };
+        println!(
+            "{}: {} -> {} ({}{})",
+            address,
+            denomination.format(*before),
+            denomination.format(*after),
+            sign,
+            denomination.format(change)
+        );
+    }
+    if changes.is_empty() {
+        println!("None");
+    }
+}
@@ -0,0 +1,421 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::types::account_address::AccountAddress;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::amount::MAX_DECIMALS;
+use crate::cli::Cli;
+use crate::network::{Network, Profile};
+use crate::output::OutputFormat;
+use crate::retry::RetryPolicy;
+
+const DEFAULT_WAIT_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_LAG_VERSIONS: u64 = 1_000;
+pub const APTOS_COIN_TYPE: &str = "0x1::aptos_coin::AptosCoin";
+
+// Settings from a single source. Every field is optional so the config file, environment
+// and flags can each fill in just the values they care about
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Layer {
+    network: Option<String>,
+    node_url: Option<String>,
+    // Several fullnodes of the network, in order of preference, in place of node_url
+    node_urls: Option<Vec<String>>,
+    faucet_url: Option<String>,
+    chain_id: Option<u8>,
+    max_lag_versions: Option<u64>,
+    default_sender: Option<String>,
+    coin_type: Option<String>,
+    decimals: Option<u8>,
+    output: Option<String>,
+    gas: GasLayer,
+    timeouts: TimeoutsLayer,
+    retry: RetryLayer,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GasLayer {
+    max_gas_amount: Option<u64>,
+    gas_unit_price: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TimeoutsLayer {
+    wait_secs: Option<u64>,
+    expiration_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RetryLayer {
+    max_attempts: Option<u32>,
+    initial_backoff_ms: Option<u64>,
+    max_backoff_ms: Option<u64>,
+    jitter: Option<f64>,
+    retryable_status_codes: Option<Vec<u16>>,
+}
+
+impl Layer {
+    // Values set in `over` win, anything it leaves out falls through to `self`. node_url and
+    // node_urls are one setting, so either one in `over` replaces both
+    fn merge(self, over: Layer) -> Layer {
+        let (node_url, node_urls) = match over.node_url.is_some() || over.node_urls.is_some() {
+            true => (over.node_url, over.node_urls),
+            false => (self.node_url, self.node_urls),
+        };
+
+        Layer {
+            network: over.network.or(self.network),
+            node_url,
+            node_urls,
+            faucet_url: over.faucet_url.or(self.faucet_url),
+            chain_id: over.chain_id.or(self.chain_id),
+            max_lag_versions: over.max_lag_versions.or(self.max_lag_versions),
+            default_sender: over.default_sender.or(self.default_sender),
+            coin_type: over.coin_type.or(self.coin_type),
+            decimals: over.decimals.or(self.decimals),
+            output: over.output.or(self.output),
+            gas: GasLayer {
+                max_gas_amount: over.gas.max_gas_amount.or(self.gas.max_gas_amount),
+                gas_unit_price: over.gas.gas_unit_price.or(self.gas.gas_unit_price),
+            },
+            timeouts: TimeoutsLayer {
+                wait_secs: over.timeouts.wait_secs.or(self.timeouts.wait_secs),
+                expiration_secs: over
+                    .timeouts
+                    .expiration_secs
+                    .or(self.timeouts.expiration_secs),
+            },
+            retry: RetryLayer {
+                max_attempts: over.retry.max_attempts.or(self.retry.max_attempts),
+                initial_backoff_ms: over
+                    .retry
+                    .initial_backoff_ms
+                    .or(self.retry.initial_backoff_ms),
+                max_backoff_ms: over.retry.max_backoff_ms.or(self.retry.max_backoff_ms),
+                jitter: over.retry.jitter.or(self.retry.jitter),
+                retryable_status_codes: over
+                    .retry
+                    .retryable_status_codes
+                    .or(self.retry.retryable_status_codes),
+            },
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Layer> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read config file {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    fn from_env() -> Result<Layer> {
+        Ok(Layer {
+            network: env_var("APTOS_NETWORK"),
+            node_url: env_var("APTOS_NODE_URL"),
+            node_urls: env_list("APTOS_NODE_URLS"),
+            faucet_url: env_var("APTOS_FAUCET_URL"),
+            chain_id: parse_env_var("APTOS_CHAIN_ID", "chain_id")?,
+            max_lag_versions: parse_env_var("APTOS_MAX_LAG_VERSIONS", "max_lag_versions")?,
+            default_sender: env_var("APTOS_DEFAULT_SENDER"),
+            coin_type: env_var("APTOS_COIN_TYPE"),
+            decimals: parse_env_var("APTOS_DECIMALS", "decimals")?,
+            output: env_var("APTOS_OUTPUT"),
+            gas: GasLayer {
+                max_gas_amount: parse_env_var("APTOS_MAX_GAS_AMOUNT", "gas.max_gas_amount")?,
+                gas_unit_price: parse_env_var("APTOS_GAS_UNIT_PRICE", "gas.gas_unit_price")?,
+            },
+            timeouts: TimeoutsLayer {
+                wait_secs: parse_env_var("APTOS_WAIT_TIMEOUT_SECS", "timeouts.wait_secs")?,
+                expiration_secs: parse_env_var(
+                    "APTOS_EXPIRATION_SECS",
+                    "timeouts.expiration_secs",
+                )?,
+            },
+            retry: RetryLayer {
+                max_attempts: parse_env_var("APTOS_RETRY_MAX_ATTEMPTS", "retry.max_attempts")?,
+                initial_backoff_ms: parse_env_var(
+                    "APTOS_RETRY_INITIAL_BACKOFF_MS",
+                    "retry.initial_backoff_ms",
+                )?,
+                max_backoff_ms: parse_env_var(
+                    "APTOS_RETRY_MAX_BACKOFF_MS",
+                    "retry.max_backoff_ms",
+                )?,
+                jitter: parse_env_var("APTOS_RETRY_JITTER", "retry.jitter")?,
+                retryable_status_codes: parse_status_codes(
+                    "APTOS_RETRY_STATUS_CODES",
+                    "retry.retryable_status_codes",
+                )?,
+            },
+        })
+    }
+
+    fn from_flags(cli: &Cli) -> Layer {
+        Layer {
+            network: cli.network.clone(),
+            // --node-url may be repeated, so it always fills node_urls
+            node_url: None,
+            node_urls: match cli.node_url.is_empty() {
+                true => None,
+                false => Some(cli.node_url.clone()),
+            },
+            faucet_url: cli.faucet_url.clone(),
+            chain_id: cli.chain_id,
+            max_lag_versions: cli.max_lag_versions,
+            default_sender: None,
+            coin_type: cli.coin_type.clone(),
+            decimals: cli.decimals,
+            output: cli.output.clone(),
+            gas: GasLayer {
+                max_gas_amount: cli.max_gas_amount,
+                gas_unit_price: cli.gas_unit_price,
+            },
+            timeouts: TimeoutsLayer {
+                wait_secs: cli.wait_timeout_secs,
+                expiration_secs: cli.expiration_secs,
+            },
+            retry: RetryLayer {
+                max_attempts: cli.max_attempts,
+                ..RetryLayer::default()
+            },
+        }
+    }
+}
+
+// Gas settings for transactions we send. Unset values leave the SDK's defaults in place
+#[derive(Debug, Clone, Default)]
+pub struct GasConfig {
+    pub max_gas_amount: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+}
+
+// Fully resolved and validated settings: built in defaults, then the config file, then
+// APTOS_* environment variables, then command line flags
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub network: Network,
+    pub default_sender: Option<String>,
+    pub coin_type: String,
+    // Overrides the decimals of whichever coin amounts are parsed and shown in
+    pub decimals: Option<u8>,
+    pub output: OutputFormat,
+    pub gas: GasConfig,
+    pub wait_timeout: Duration,
+    // Unset leaves the SDK's default expiration in place
+    pub expiration_secs: Option<u64>,
+    pub retry: RetryPolicy,
+}
+
+impl Config {
+    pub fn load(cli: &Cli) -> Result<Self> {
+        let mut layer = Layer::default();
+
+        // An explicitly named config file has to exist, the default one is optional
+        match explicit_path(cli) {
+            Some(path) => layer = layer.merge(Layer::from_file(&path)?),
+            None => {
+                if let Some(path) = default_path().filter(|path| path.exists()) {
+                    layer = layer.merge(Layer::from_file(&path)?);
+                }
+            }
+        }
+        layer = layer.merge(Layer::from_env()?);
+        layer = layer.merge(Layer::from_flags(cli));
+
+        Self::validate(layer)
+    }
+
+    fn validate(layer: Layer) -> Result<Self> {
+        let profile = match &layer.network {
+            Some(name) => name
+                .parse::<Profile>()
+                .context("Invalid value for network")?,
+            None => Profile::Devnet,
+        };
+        let node_urls = match (layer.node_url, layer.node_urls) {
+            (Some(_), Some(_)) => bail!("Set either node_url or node_urls, not both"),
+            (_, Some(urls)) if urls.is_empty() => {
+                bail!("Invalid value for node_urls: must list at least one node")
+            }
+            (Some(url), None) => vec![url],
+            (None, urls) => urls.unwrap_or_default(),
+        };
+        let network = Network::resolve(
+            profile,
+            &node_urls,
+            layer.faucet_url.as_deref(),
+            layer.chain_id,
+            layer.max_lag_versions.unwrap_or(DEFAULT_MAX_LAG_VERSIONS),
+        )?;
+
+        let coin_type = match layer.coin_type {
+            Some(coin_type) => {
+                validate_coin_type(&coin_type).context("Invalid value for coin_type")?;
+                coin_type
+            }
+            None => APTOS_COIN_TYPE.to_string(),
+        };
+
+        if let Some(decimals) = layer.decimals {
+            if decimals > MAX_DECIMALS {
+                bail!(
+                    "Invalid value for decimals: must be at most {}",
+                    MAX_DECIMALS
+                );
+            }
+        }
+
+        let output = match &layer.output {
+            Some(name) => name.parse().context("Invalid value for output")?,
+            None => OutputFormat::Table,
+        };
+
+        if layer.gas.max_gas_amount == Some(0) {
+            bail!("Invalid value for gas.max_gas_amount: must be greater than 0");
+        }
+        if layer.gas.gas_unit_price == Some(0) {
+            bail!("Invalid value for gas.gas_unit_price: must be greater than 0");
+        }
+        if layer.timeouts.expiration_secs == Some(0) {
+            bail!("Invalid value for timeouts.expiration_secs: must be greater than 0");
+        }
+        let wait_secs = layer
+            .timeouts
+            .wait_secs
+            .unwrap_or(DEFAULT_WAIT_TIMEOUT_SECS);
+        if wait_secs == 0 {
+            bail!("Invalid value for timeouts.wait_secs: must be greater than 0");
+        }
+        let retry = validate_retry(layer.retry)?;
+
+        Ok(Self {
+            network,
+            default_sender: layer.default_sender,
+            coin_type,
+            decimals: layer.decimals,
+            output,
+            gas: GasConfig {
+                max_gas_amount: layer.gas.max_gas_amount,
+                gas_unit_price: layer.gas.gas_unit_price,
+            },
+            expiration_secs: layer.timeouts.expiration_secs,
+            wait_timeout: Duration::from_secs(wait_secs),
+            retry,
+        })
+    }
+}
+
+fn validate_retry(layer: RetryLayer) -> Result<RetryPolicy> {
+    let defaults = RetryPolicy::default();
+    let retry = RetryPolicy {
+        max_attempts: layer.max_attempts.unwrap_or(defaults.max_attempts),
+        initial_backoff: layer
+            .initial_backoff_ms
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.initial_backoff),
+        max_backoff: layer
+            .max_backoff_ms
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.max_backoff),
+        jitter: layer.jitter.unwrap_or(defaults.jitter),
+        retryable_status_codes: layer
+            .retryable_status_codes
+            .unwrap_or(defaults.retryable_status_codes),
+    };
+
+    if retry.max_attempts == 0 {
+        bail!("Invalid value for retry.max_attempts: must be at least 1");
+    }
+    if retry.max_backoff < retry.initial_backoff {
+        bail!("Invalid value for retry.max_backoff_ms: must be at least retry.initial_backoff_ms");
+    }
+    if !(0.0..1.0).contains(&retry.jitter) {
+        bail!("Invalid value for retry.jitter: must be at least 0 and less than 1");
+    }
+    if let Some(status) = retry
+        .retryable_status_codes
+        .iter()
+        .find(|status| !(100..600).contains(*status))
+    {
+        bail!(
+            "Invalid value for retry.retryable_status_codes: {} is not an HTTP status",
+            status
+        );
+    }
+
+    Ok(retry)
+}
+
+// A coin type is a Move struct tag, `<address>::<module>::<struct>`, where the struct may
+// carry type arguments
+fn validate_coin_type(coin_type: &str) -> Result<()> {
+    let parts: Vec<&str> = coin_type.splitn(3, "::").collect();
+    if parts.len() != 3 || parts.iter().any(|part| part.is_empty()) {
+        bail!(
+            "'{}' is not of the form <address>::<module>::<struct>",
+            coin_type
+        );
+    }
+    AccountAddress::from_hex_literal(parts[0])
+        .with_context(|| format!("'{}' has an invalid address", coin_type))?;
+
+    Ok(())
+}
+
+fn explicit_path(cli: &Cli) -> Option<PathBuf> {
+    cli.config
+        .clone()
+        .or_else(|| env_var("APTOS_CONFIG").map(PathBuf::from))
+}
+
+fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".aptos-client").join("config.toml"))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+// A comma separated list such as `http://node-1:8080,http://node-2:8080`
+fn env_list(name: &str) -> Option<Vec<String>> {
+    env_var(name).map(|value| {
+        value
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    })
+}
+
+// A comma separated list such as `429,503`
+fn parse_status_codes(name: &str, field: &str) -> Result<Option<Vec<u16>>> {
+    match env_var(name) {
+        Some(value) => value
+            .split(',')
+            .map(|status| status.trim().parse())
+            .collect::<Result<_, _>>()
+            .map(Some)
+            .with_context(|| format!("Invalid value for {} in ${}: '{}'", field, name, value)),
+        None => Ok(None),
+    }
+}
+
+fn parse_env_var<T>(name: &str, field: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match env_var(name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .with_context(|| format!("Invalid value for {} in ${}: '{}'", field, name, value)),
+        None => Ok(None),
+    }
+}
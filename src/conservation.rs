@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use aptos_sdk::types::account_address::AccountAddress;
+use std::fmt;
+
+use crate::coin;
+use crate::config::APTOS_COIN_TYPE;
+use crate::pool::NodePool;
+use crate::receipt::Receipt;
+use crate::retry::RetryPolicy;
+
+// The sender's and receiver's balances of the transferred coin, read together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Balances {
+    pub sender: u64,
+    pub receiver: u64,
+}
+
+impl Balances {
+    // Accounts without a CoinStore hold nothing
+    pub async fn fetch(
+        nodes: &NodePool,
+        retry: &RetryPolicy,
+        sender: AccountAddress,
+        receiver: AccountAddress,
+        coin_type: &str,
+    ) -> Result<Self> {
+        Ok(Self {
+            sender: coin::balance(nodes, retry, sender, coin_type)
+                .await
+                .context("Could not fetch sender's balance")?
+                .unwrap_or(0),
+            receiver: coin::balance(nodes, retry, receiver, coin_type)
+                .await
+                .context("Could not fetch receiver's balance")?
+                .unwrap_or(0),
+        })
+    }
+}
+
+// What a committed transfer should have done to the balances around it:
+//
+//     sender_before - amount - fee == sender_after
+//     receiver_before + amount == receiver_after
+//
+// Gas is paid in APT, so the fee only comes out of the sender's balance of APT. A transfer
+// that aborted still pays its fee but moves nothing, and one to the sender itself only
+// costs the fee
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conservation {
+    pub before: Balances,
+    pub after: Balances,
+    pub amount: u64,
+    pub fee: u64,
+    // The sender paid itself, so there is only one balance to check
+    pub self_transfer: bool,
+}
+
+// One balance that came out different from what the transfer should have left
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub account: &'static str,
+    pub expected: i128,
+    pub actual: u64,
+    // How the expected balance was worked out, e.g. "20000 - 1000 - 10"
+    pub working: String,
+}
+
+impl Conservation {
+    pub fn new(
+        before: Balances,
+        after: Balances,
+        amount: u64,
+        receipt: &Receipt,
+        sender: AccountAddress,
+        receiver: AccountAddress,
+        coin_type: &str,
+    ) -> Self {
+        let self_transfer = sender == receiver;
+        let amount = match receipt.success && !self_transfer {
+            true => amount,
+            false => 0,
+        };
+        let fee = match coin_type == APTOS_COIN_TYPE {
+            true => receipt.fee(),
+            false => 0,
+        };
+
+        Self {
+            before,
+            after,
+            amount,
+            fee,
+            self_transfer,
+        }
+    }
+
+    pub fn mismatches(&self) -> Vec<Mismatch> {
+        let sender = Mismatch {
+            account: "sender",
+            expected: i128::from(self.before.sender)
+                - i128::from(self.amount)
+                - i128::from(self.fee),
+            actual: self.after.sender,
+            working: format!("{} - {} - {}", self.before.sender, self.amount, self.fee),
+        };
+        let receiver = Mismatch {
+            account: "receiver",
+            expected: i128::from(self.before.receiver) + i128::from(self.amount),
+            actual: self.after.receiver,
+            working: format!("{} + {}", self.before.receiver, self.amount),
+        };
+
+        let checked = match self.self_transfer {
+            true => vec![sender],
+            false => vec![sender, receiver],
+        };
+        checked
+            .into_iter()
+            .filter(|mismatch| mismatch.expected != i128::from(mismatch.actual))
+            .collect()
+    }
+
+    pub fn holds(&self) -> bool {
+        self.mismatches().is_empty()
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let off_by = i128::from(self.actual) - self.expected;
+        write!(
+            f,
+            "{} balance: expected {} ({}), actual {} (off by {:+})",
+            self.account, self.expected, self.working, self.actual, off_by
+        )
+    }
+}
+
+impl fmt::Display for Conservation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mismatches = self.mismatches();
+        if mismatches.is_empty() {
+            return write!(f, "balances conserved");
+        }
+
+        write!(
+            f,
+            "balances not conserved for a transfer of {} with a fee of {}",
+            self.amount, self.fee
+        )?;
+        for mismatch in mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+use thiserror::Error;
+
+use crate::preflight::PreflightError;
+use crate::retry::RetryPolicy;
+
+// Why a command failed, for the categories scripts care about. Each one exits with its own
+// code; anything else exits with 1, and clap exits with 2 on usage errors
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ClientError {
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("faucet error: {0}")]
+    Faucet(String),
+    #[error("insufficient balance: {0}")]
+    InsufficientBalance(String),
+    #[error("sequence number mismatch: {0}")]
+    SequenceMismatch(String),
+    #[error("transaction expired: {0}")]
+    Expired(String),
+    #[error("account not found: {0}")]
+    AccountNotFound(String),
+    #[error("{0}")]
+    MoveAbort(MoveAbort),
+    #[error("timed out: {0}")]
+    Timeout(String),
+    // The VM failed the transaction for a reason other than an abort, such as running out of
+    // gas
+    #[error("transaction failed: {0}")]
+    ExecutionFailed(String),
+}
+
+// Validation statuses the node rejects a submission with, and what they mean for the sender
+const VALIDATION_ERRORS: [(&str, fn(String) -> ClientError, &str); 5] = [
+    (
+        "SEQUENCE_NUMBER_TOO_OLD",
+        ClientError::SequenceMismatch,
+        "the sequence number was already used, run the command again to pick up the current one",
+    ),
+    (
+        "SEQUENCE_NUMBER_TOO_NEW",
+        ClientError::SequenceMismatch,
+        "the sequence number is ahead of the account's, an earlier transaction is missing",
+    ),
+    (
+        "TRANSACTION_EXPIRED",
+        ClientError::Expired,
+        "the transaction's expiration time passed before it was accepted",
+    ),
+    (
+        "INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE",
+        ClientError::InsufficientBalance,
+        "the sender cannot pay max_gas_amount x gas_unit_price in gas",
+    ),
+    (
+        "SENDING_ACCOUNT_DOES_NOT_EXIST",
+        ClientError::AccountNotFound,
+        "the sender has no onchain account",
+    ),
+];
+
+impl ClientError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ClientError::Network(_) => 3,
+            ClientError::Faucet(_) => 4,
+            ClientError::InsufficientBalance(_) => 5,
+            ClientError::SequenceMismatch(_) => 6,
+            ClientError::Expired(_) => 7,
+            ClientError::AccountNotFound(_) => 8,
+            ClientError::MoveAbort(_) => 9,
+            ClientError::Timeout(_) => 10,
+            ClientError::ExecutionFailed(_) => 11,
+        }
+    }
+
+    pub fn faucet(err: anyhow::Error) -> Self {
+        ClientError::Faucet(format!("{:#}", err))
+    }
+
+    // The error for a committed transaction that did not succeed, from its VM status
+    pub fn from_vm_status(vm_status: &str) -> Self {
+        match MoveAbort::parse(vm_status) {
+            Some(abort) => abort.into_error(),
+            None => ClientError::ExecutionFailed(vm_status.to_string()),
+        }
+    }
+
+    // The category of the first error in `err`'s chain that was raised with one, if any
+    pub fn typed(err: &anyhow::Error) -> Option<Self> {
+        err.chain()
+            .find_map(|cause| match cause.downcast_ref::<PreflightError>() {
+                Some(failure) => Some(failure.category()),
+                None => cause.downcast_ref::<ClientError>().cloned(),
+            })
+    }
+
+    // The category of `err`: a typed error somewhere in its chain, or else one read from the
+    // messages of the SDK's errors, which carry the node's VM and validation statuses only
+    // as text. Failures `retry` would have retried are network errors
+    pub fn classify(err: &anyhow::Error, retry: &RetryPolicy) -> Option<Self> {
+        if let Some(error) = Self::typed(err) {
+            return Some(error);
+        }
+
+        let message = err
+            .chain()
+            .map(|cause| format!("{}\n{:?}", cause, cause))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if let Some(abort) = MoveAbort::parse(&message) {
+            return Some(abort.into_error());
+        }
+        if let Some((_, error, reason)) = VALIDATION_ERRORS
+            .iter()
+            .find(|(status, _, _)| message.contains(status))
+        {
+            return Some(error(reason.to_string()));
+        }
+        if message.contains("Account not found") {
+            return Some(ClientError::AccountNotFound(
+                "there is no onchain account at that address".to_string(),
+            ));
+        }
+        if message.contains("was not committed within") {
+            return Some(ClientError::Timeout(
+                "the transaction may still commit, check its hash before sending again".to_string(),
+            ));
+        }
+        if message.contains("No healthy node") || retry.is_retryable(err) {
+            return Some(ClientError::Network(
+                "the node could not be reached or is unavailable".to_string(),
+            ));
+        }
+
+        None
+    }
+}
+
+// A Move abort decoded from a VM status such as `Move abort in 0x1::coin:
+// EINSUFFICIENT_BALANCE(0x10006): Not enough coins to complete transaction`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveAbort {
+    pub module: String,
+    // The error constant's name, which newer nodes include
+    pub name: Option<String>,
+    pub code: u64,
+    // A readable explanation of the abort
+    pub reason: String,
+}
+
+// Aborts worth explaining: module, reason (the low 16 bits of the code), constant name, what
+// it means, and the category it belongs to if it is more specific than a Move abort
+type KnownAbort = (
+    &'static str,
+    u64,
+    &'static str,
+    &'static str,
+    Option<fn(String) -> ClientError>,
+);
+
+const KNOWN_ABORTS: [KnownAbort; 8] = [
+    (
+        "0x1::coin",
+        3,
+        "ECOIN_INFO_NOT_PUBLISHED",
+        "the coin type has not been initialized",
+        None,
+    ),
+    (
+        "0x1::coin",
+        5,
+        "ECOIN_STORE_NOT_PUBLISHED",
+        "the account does not exist or has not registered the coin",
+        Some(ClientError::AccountNotFound),
+    ),
+    (
+        "0x1::coin",
+        6,
+        "EINSUFFICIENT_BALANCE",
+        "the sender does not hold enough of the coin for this transfer",
+        Some(ClientError::InsufficientBalance),
+    ),
+    (
+        "0x1::coin",
+        10,
+        "EFROZEN",
+        "the account's CoinStore for the coin is frozen",
+        None,
+    ),
+    (
+        "0x1::account",
+        1,
+        "EACCOUNT_ALREADY_EXISTS",
+        "the account already exists",
+        None,
+    ),
+    (
+        "0x1::account",
+        2,
+        "EACCOUNT_DOES_NOT_EXIST",
+        "the account does not exist",
+        Some(ClientError::AccountNotFound),
+    ),
+    (
+        "0x1::aptos_account",
+        1,
+        "EACCOUNT_NOT_FOUND",
+        "the recipient account does not exist",
+        Some(ClientError::AccountNotFound),
+    ),
+    (
+        "0x1::aptos_account",
+        2,
+        "EACCOUNT_NOT_REGISTERED_FOR_APT",
+        "the recipient has not registered to receive APT",
+        Some(ClientError::AccountNotFound),
+    ),
+];
+
+// The error categories in the high byte of an abort code, as the Move standard library's
+// `error` module defines them
+const CATEGORIES: [&str; 13] = [
+    "invalid argument",
+    "out of range",
+    "invalid state",
+    "unauthenticated",
+    "permission denied",
+    "not found",
+    "aborted",
+    "already exists",
+    "resource exhausted",
+    "cancelled",
+    "internal error",
+    "not implemented",
+    "unavailable",
+];
+
+impl MoveAbort {
+    pub fn parse(text: &str) -> Option<Self> {
+        const PREFIX: &str = "Move abort in ";
+        let rest = &text[text.find(PREFIX)? + PREFIX.len()..];
+        let (module, rest) = rest.split_once(": ")?;
+        let (token, description) = match rest.split_once(':') {
+            Some((token, description)) => (token.trim(), description.lines().next()),
+            None => (rest.lines().next()?.trim(), None),
+        };
+        let (name, code) = match token.split_once('(') {
+            Some((name, code)) => (Some(name.to_string()), code.trim_end_matches(')')),
+            None => (None, token),
+        };
+        let code = match code.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+            None => code.parse().ok()?,
+        };
+        let module = normalize_module(module);
+
+        let reason = match known(&module, name.as_deref(), code) {
+            Some((_, _, _, reason, _)) => reason.to_string(),
+            None => match description.map(str::trim).filter(|text| !text.is_empty()) {
+                Some(description) => description.to_string(),
+                None => describe_code(code),
+            },
+        };
+
+        Some(Self {
+            module,
+            name,
+            code,
+            reason,
+        })
+    }
+
+    // The category this abort belongs to, carrying the abort as its message
+    pub fn into_error(self) -> ClientError {
+        match known(&self.module, self.name.as_deref(), self.code).and_then(|known| known.4) {
+            Some(category) => category(self.to_string()),
+            None => ClientError::MoveAbort(self),
+        }
+    }
+}
+
+impl fmt::Display for MoveAbort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(
+                f,
+                "Move abort in {}: {} ({:#x}): {}",
+                self.module, name, self.code, self.reason
+            ),
+            None => write!(
+                f,
+                "Move abort in {} with code {:#x}: {}",
+                self.module, self.code, self.reason
+            ),
+        }
+    }
+}
+
+// Match by constant name when the node gave one, since reason numbers have been renumbered
+// between framework releases
+fn known(module: &str, name: Option<&str>, code: u64) -> Option<&'static KnownAbort> {
+    KNOWN_ABORTS
+        .iter()
+        .find(|(known_module, reason, known_name, _, _)| {
+            *known_module == module
+                && match name {
+                    Some(name) => name == *known_name,
+                    None => code & 0xffff == *reason,
+                }
+        })
+}
+
+// `<category>, reason <n>` for aborts we know nothing more about
+fn describe_code(code: u64) -> String {
+    let category = (code >> 16) as usize;
+    match category
+        .checked_sub(1)
+        .and_then(|index| CATEGORIES.get(index))
+    {
+        Some(category) => format!("{}, reason {}", category, code & 0xffff),
+        None => format!("reason {}", code),
+    }
+}
+
+// Modules appear with short (0x1) or zero padded addresses
+fn normalize_module(module: &str) -> String {
+    match module.trim().split_once("::") {
+        Some((address, name)) => {
+            let hex = address.trim_start_matches("0x").trim_start_matches('0');
+            format!("0x{}::{}", if hex.is_empty() { "0" } else { hex }, name)
+        }
+        None => module.trim().to_string(),
+    }
+}
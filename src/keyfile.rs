@@ -0,0 +1,144 @@
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_sdk::crypto::ed25519::Ed25519PrivateKey;
+use aptos_sdk::types::account_address::AccountAddress;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+const VERSION: u8 = 1;
+const KDF: &str = "scrypt";
+const CIPHER: &str = "aes-256-gcm";
+
+// scrypt cost parameters for newly encrypted keys, roughly 32 MiB and a fraction of a second
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub name: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+// A private key encrypted with a password derived key. The account address is bound in as
+// associated data so a key file cannot be swapped onto another account unnoticed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedKey {
+    pub version: u8,
+    pub kdf: KdfParams,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl EncryptedKey {
+    pub fn encrypt(
+        private_key: &Ed25519PrivateKey,
+        address: AccountAddress,
+        password: &str,
+    ) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+        let kdf = KdfParams {
+            name: KDF.to_string(),
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: hex::encode(salt),
+        };
+        let cipher = Aes256Gcm::new(Key::from_slice(&derive_key(&kdf, password)?));
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &private_key.to_bytes(),
+                    aad: address.as_ref(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt private key"))?;
+
+        Ok(Self {
+            version: VERSION,
+            kdf,
+            cipher: CIPHER.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, address: AccountAddress, password: &str) -> Result<Ed25519PrivateKey> {
+        if self.version != VERSION {
+            bail!("Unsupported key file version {}", self.version);
+        }
+        if self.cipher != CIPHER {
+            bail!("Unsupported key file cipher '{}'", self.cipher);
+        }
+
+        let nonce = hex::decode(&self.nonce).context("Key file nonce is not valid hex")?;
+        if nonce.len() != NONCE_LEN {
+            bail!(
+                "Key file nonce has length {}, expected {}",
+                nonce.len(),
+                NONCE_LEN
+            );
+        }
+        let ciphertext =
+            hex::decode(&self.ciphertext).context("Key file ciphertext is not valid hex")?;
+
+        let cipher = Aes256Gcm::new(Key::from_slice(&derive_key(&self.kdf, password)?));
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: address.as_ref(),
+                },
+            )
+            .map_err(|_| anyhow!("Wrong password or corrupted key file"))?;
+
+        Ed25519PrivateKey::try_from(plaintext.as_slice())
+            .context("Decrypted key is not a valid ed25519 key")
+    }
+}
+
+fn derive_key(kdf: &KdfParams, password: &str) -> Result<[u8; KEY_LEN]> {
+    if kdf.name != KDF {
+        bail!("Unsupported key file KDF '{}'", kdf.name);
+    }
+
+    let salt = hex::decode(&kdf.salt).context("Key file salt is not valid hex")?;
+    let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p)
+        .map_err(|err| anyhow!("Invalid scrypt parameters in key file: {}", err))?;
+
+    let mut key = [0u8; KEY_LEN];
+    scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key)
+        .map_err(|err| anyhow!("Failed to derive key from password: {}", err))?;
+
+    Ok(key)
+}
+
+// Parse a hex encoded ed25519 private key, with or without a 0x prefix
+pub fn parse_private_key(encoded: &str) -> Result<Ed25519PrivateKey> {
+    let bytes = hex::decode(encoded.trim().trim_start_matches("0x"))
+        .context("Private key is not valid hex")?;
+    Ed25519PrivateKey::try_from(bytes.as_slice()).context("Private key is not a valid ed25519 key")
+}
+
+// Portable form of a keystore account written by `export` and read by `import`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub address: String,
+    pub encrypted_key: EncryptedKey,
+}
@@ -0,0 +1,270 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::crypto::ed25519::Ed25519PrivateKey;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::keyfile::{parse_private_key, EncryptedKey};
+
+// A named account as it is written to disk, one JSON file per account. Keys are stored
+// encrypted; `private_key` only exists on files written before encryption was added and is
+// dropped the next time the key is encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAccount {
+    pub name: String,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_key: Option<EncryptedKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    pub sequence_number: u64,
+}
+
+impl StoredAccount {
+    fn from_account(name: &str, account: &LocalAccount, password: &str) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            address: account.address().to_hex_literal(),
+            encrypted_key: Some(EncryptedKey::encrypt(
+                account.private_key(),
+                account.address(),
+                password,
+            )?),
+            private_key: None,
+            sequence_number: account.sequence_number(),
+        })
+    }
+
+    pub fn address(&self) -> Result<AccountAddress> {
+        AccountAddress::from_str(&self.address)
+            .with_context(|| format!("Account '{}' has an invalid address", self.name))
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted_key.is_some()
+    }
+
+    // Decrypt the private key. The password is only needed for encrypted keys
+    pub fn private_key(&self, password: Option<&str>) -> Result<Ed25519PrivateKey> {
+        match (&self.encrypted_key, &self.private_key, password) {
+            (Some(encrypted_key), _, Some(password)) => encrypted_key
+                .decrypt(self.address()?, password)
+                .with_context(|| format!("Could not decrypt key of account '{}'", self.name)),
+            (Some(_), _, None) => bail!("Account '{}' needs a password", self.name),
+            (None, Some(private_key), _) => parse_private_key(private_key)
+                .with_context(|| format!("Account '{}' has an invalid private key", self.name)),
+            (None, None, _) => bail!("Account '{}' has no private key", self.name),
+        }
+    }
+
+    pub fn into_account(self, password: Option<&str>) -> Result<LocalAccount> {
+        let address = self.address()?;
+        let private_key = self.private_key(password)?;
+
+        Ok(LocalAccount::new(
+            address,
+            private_key,
+            self.sequence_number,
+        ))
+    }
+}
+
+// Directory of named accounts so keys (and the coins they hold) survive between runs
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Could not create keystore directory {}", dir.display()))?;
+
+        Ok(Self { dir })
+    }
+
+    // Use APTOS_KEYSTORE environment variable to set the keystore directory or default to
+    // ~/.aptos-client/keystore
+    pub fn default_dir() -> Result<PathBuf> {
+        if let Ok(dir) = std::env::var("APTOS_KEYSTORE") {
+            return Ok(PathBuf::from(dir));
+        }
+
+        let home = dirs::home_dir().context("Could not determine home directory for keystore")?;
+        Ok(home.join(".aptos-client").join("keystore"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        validate_name(name).is_ok() && self.path(name).exists()
+    }
+
+    // Save a new named account with its key encrypted, refusing to overwrite an existing one
+    pub fn add(&self, name: &str, account: &LocalAccount, password: &str) -> Result<()> {
+        self.ensure_new(name)?;
+        self.write(&StoredAccount::from_account(name, account, password)?)
+    }
+
+    // Save a new named account whose key is already encrypted, e.g. an imported key file
+    pub fn add_encrypted(
+        &self,
+        name: &str,
+        address: AccountAddress,
+        encrypted_key: EncryptedKey,
+    ) -> Result<()> {
+        self.ensure_new(name)?;
+        self.write(&StoredAccount {
+            name: name.to_string(),
+            address: address.to_hex_literal(),
+            encrypted_key: Some(encrypted_key),
+            private_key: None,
+            sequence_number: 0,
+        })
+    }
+
+    // Re-encrypt an account's key, replacing any plaintext key left from older files
+    pub fn set_private_key(
+        &self,
+        name: &str,
+        private_key: &Ed25519PrivateKey,
+        password: &str,
+    ) -> Result<()> {
+        let mut stored = self.get(name)?;
+        stored.encrypted_key = Some(EncryptedKey::encrypt(
+            private_key,
+            stored.address()?,
+            password,
+        )?);
+        stored.private_key = None;
+
+        self.write(&stored)
+    }
+
+    pub fn get(&self, name: &str) -> Result<StoredAccount> {
+        validate_name(name)?;
+        let path = self.path(name);
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Account '{}' not found in keystore", name))?;
+
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Could not parse keystore file {}", path.display()))
+    }
+
+    // Persist the account's current sequence number as the last known one
+    pub fn save_sequence_number(&self, name: &str, account: &LocalAccount) -> Result<()> {
+        let mut stored = self.get(name)?;
+        stored.sequence_number = account.sequence_number();
+
+        self.write(&stored)
+    }
+
+    pub fn list(&self) -> Result<Vec<StoredAccount>> {
+        let mut accounts = Vec::new();
+        let entries = fs::read_dir(&self.dir)
+            .with_context(|| format!("Could not read keystore directory {}", self.dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                accounts.push(self.get(name)?);
+            }
+        }
+
+        accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(accounts)
+    }
+
+    fn ensure_new(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        if self.path(name).exists() {
+            bail!("Account '{}' already exists in keystore", name);
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    fn write(&self, stored: &StoredAccount) -> Result<()> {
+        let path = self.path(&stored.name);
+        let contents = serde_json::to_string_pretty(stored)?;
+        write_secret(&path, contents.as_bytes())
+            .with_context(|| format!("Could not write keystore file {}", path.display()))
+    }
+}
+
+// Write a file holding key material, readable by the owner only from the moment it exists.
+// The contents go to a temporary file beside it first, which is synced and then renamed over
+// the old file, so a crash or a full disk part way through leaves the old key in place
+pub fn write_secret(path: &Path, contents: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file path", path.display()))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let written = write_new_secret(&temp_path, contents)
+        .and_then(|()| fs::rename(&temp_path, path).map_err(Into::into));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written?;
+
+    // The rename itself only lasts once the directory holding it is synced
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+fn write_new_secret(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "Invalid account name '{}': use letters, digits, '-' and '_' only",
+            name
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+// The client as a library: the command line app in `commands`, and the pieces it is built
+// from for anyone driving transfers from their own code or tests
+pub mod account;
+pub mod amount;
+pub mod api_json;
+pub mod batch;
+pub mod bench;
+pub mod cli;
+pub mod coin;
+pub mod commands;
+pub mod config;
+pub mod conservation;
+pub mod error;
+pub mod keyfile;
+pub mod keystore;
+pub mod mnemonic;
+pub mod network;
+pub mod offline;
+pub mod output;
+pub mod password;
+pub mod pool;
+pub mod preflight;
+pub mod receipt;
+pub mod retry;
+pub mod scenario;
+pub mod scenario_file;
+pub mod sequence;
+pub mod summary;
+pub mod transaction;
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Context, Result};
+use aptos_sdk::crypto::ed25519::Ed25519PrivateKey;
+use aptos_sdk::crypto::PrivateKey;
+use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+use aptos_sdk::types::LocalAccount;
+use bip39::{Language, Mnemonic, Seed};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+type HmacSha512 = Hmac<Sha512>;
+
+// Aptos' registered SLIP-0044 coin type
+const APTOS_COIN_TYPE: u32 = 637;
+const HARDENED: u32 = 0x8000_0000;
+
+// Read a BIP-39 phrase from a file, the APTOS_MNEMONIC environment variable, or a prompt
+pub fn read_phrase(file: Option<&Path>) -> Result<String> {
+    let phrase = match file {
+        Some(file) => fs::read_to_string(file)
+            .with_context(|| format!("Could not read mnemonic file {}", file.display()))?,
+        None => match std::env::var("APTOS_MNEMONIC") {
+            Ok(phrase) => phrase,
+            Err(_) => rpassword::prompt_password("Mnemonic phrase: ")
+                .context("Could not read mnemonic phrase")?,
+        },
+    };
+
+    // Normalise whitespace so phrases copied across lines still parse
+    Ok(phrase.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+// The standard Aptos derivation path for an account index
+pub fn derivation_path(index: u32) -> String {
+    format!("m/44'/{}'/{}'/0'/0'", APTOS_COIN_TYPE, index)
+}
+
+// Derive the ed25519 key at m/44'/637'/index'/0'/0' following SLIP-0010, where every
+// level is hardened since ed25519 has no public child derivation
+pub fn derive_private_key(phrase: &str, index: u32) -> Result<Ed25519PrivateKey> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+        .map_err(|err| anyhow!("Invalid mnemonic phrase: {}", err))?;
+    let seed = Seed::new(&mnemonic, "");
+
+    let (mut key, mut chain_code) = split(hmac_sha512(b"ed25519 seed", &[seed.as_bytes()]));
+    for level in [44, APTOS_COIN_TYPE, index, 0, 0] {
+        let child = (level | HARDENED).to_be_bytes();
+        (key, chain_code) = split(hmac_sha512(&chain_code, &[&[0u8], &key, &child]));
+    }
+
+    Ed25519PrivateKey::try_from(&key[..]).context("Derived key is not a valid ed25519 key")
+}
+
+// A LocalAccount for a derived key. The sequence number starts at 0 and should be
+// synced from chain before sending
+pub fn derive_account(phrase: &str, index: u32) -> Result<LocalAccount> {
+    let private_key = derive_private_key(phrase, index)?;
+    let address = AuthenticationKey::ed25519(&private_key.public_key()).derived_address();
+
+    Ok(LocalAccount::new(address, private_key, 0))
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+
+    let mut output = [0u8; 64];
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    output
+}
+
+fn split(bytes: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut key = [0u8; 32];
+    let mut chain_code = [0u8; 32];
+    key.copy_from_slice(&bytes[..32]);
+    chain_code.copy_from_slice(&bytes[32..]);
+
+    (key, chain_code)
+}
@@ -0,0 +1,177 @@
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::str::FromStr;
+use url::Url;
+
+use crate::pool::{Health, NodePool};
+
+// The networks the tool knows how to reach. `Custom` has no built in endpoints and is
+// described entirely by the node_url (or node_urls), faucet_url and chain_id settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Devnet,
+    Testnet,
+    Mainnet,
+    Local,
+    Custom,
+}
+
+impl Profile {
+    fn node_url(self) -> Option<&'static str> {
+        match self {
+            Profile::Devnet => Some("https://fullnode.devnet.aptoslabs.com"),
+            Profile::Testnet => Some("https://fullnode.testnet.aptoslabs.com"),
+            Profile::Mainnet => Some("https://fullnode.mainnet.aptoslabs.com"),
+            Profile::Local => Some("http://127.0.0.1:8080"),
+            Profile::Custom => None,
+        }
+    }
+
+    fn faucet_url(self) -> Option<&'static str> {
+        match self {
+            Profile::Devnet => Some("https://faucet.devnet.aptoslabs.com"),
+            Profile::Testnet => Some("https://faucet.testnet.aptoslabs.com"),
+            Profile::Local => Some("http://127.0.0.1:8081"),
+            Profile::Mainnet | Profile::Custom => None,
+        }
+    }
+
+    // Devnet is wiped and restarted with a new chain id regularly, so it is not pinned
+    fn chain_id(self) -> Option<u8> {
+        match self {
+            Profile::Mainnet => Some(1),
+            Profile::Testnet => Some(2),
+            Profile::Local => Some(4),
+            Profile::Devnet | Profile::Custom => None,
+        }
+    }
+
+    // Chain ids a network without a pinned one can still never be on. Whatever devnet's id is
+    // this week, it is not mainnet's or testnet's, so a devnet profile pointed at one of those
+    // nodes is caught before it spends real coins
+    fn excluded_chain_ids(self) -> &'static [u8] {
+        match self {
+            Profile::Devnet => &[1, 2],
+            _ => &[],
+        }
+    }
+}
+
+impl FromStr for Profile {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "devnet" => Ok(Profile::Devnet),
+            "testnet" => Ok(Profile::Testnet),
+            "mainnet" => Ok(Profile::Mainnet),
+            "local" => Ok(Profile::Local),
+            "custom" => Ok(Profile::Custom),
+            _ => bail!(
+                "Unknown network '{}', expected devnet, testnet, mainnet, local or custom",
+                name
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Profile::Devnet => "devnet",
+            Profile::Testnet => "testnet",
+            Profile::Mainnet => "mainnet",
+            Profile::Local => "local",
+            Profile::Custom => "custom",
+        };
+        f.write_str(name)
+    }
+}
+
+// A resolved network: a profile's endpoints with any overrides applied
+#[derive(Debug, Clone)]
+pub struct Network {
+    pub profile: Profile,
+    // In order of preference
+    pub node_urls: Vec<Url>,
+    pub faucet_url: Option<Url>,
+    pub chain_id: Option<u8>,
+    // Refused when no chain id is pinned
+    pub excluded_chain_ids: &'static [u8],
+    // How far behind the newest node a node may be and still take requests
+    pub max_lag_versions: u64,
+}
+
+impl Network {
+    // Start from the profile's endpoints and replace whichever ones were overridden
+    pub fn resolve(
+        profile: Profile,
+        node_urls: &[String],
+        faucet_url: Option<&str>,
+        chain_id: Option<u8>,
+        max_lag_versions: u64,
+    ) -> Result<Self> {
+        let node_urls = match (node_urls, profile.node_url()) {
+            ([], Some(url)) => vec![parse_url(url, "node_url")?],
+            ([], None) => bail!(
+                "The {} network needs a node_url from the config file, $APTOS_NODE_URL or --node-url",
+                profile
+            ),
+            (urls, _) => urls
+                .iter()
+                .map(|url| parse_url(url, "node_url"))
+                .collect::<Result<_>>()?,
+        };
+        let faucet_url = match faucet_url.or_else(|| profile.faucet_url()) {
+            Some(url) => Some(parse_url(url, "faucet_url")?),
+            None => None,
+        };
+
+        Ok(Self {
+            profile,
+            node_urls,
+            faucet_url,
+            chain_id: chain_id.or_else(|| profile.chain_id()),
+            excluded_chain_ids: profile.excluded_chain_ids(),
+            max_lag_versions,
+        })
+    }
+
+    pub fn node_pool(&self) -> NodePool {
+        NodePool::new(self.node_urls.clone())
+    }
+
+    // Health check every node before touching any account, so requests only go to nodes that
+    // are up, on the chain this network expects and caught up. Networks without a pinned
+    // chain id take the chain of the first node that answers, short of an excluded one.
+    // Returns the unhealthy nodes that will be skipped, and only if none is left is it an error
+    pub async fn check_nodes(&self, pool: &NodePool) -> Result<Vec<(Url, Health)>> {
+        let health = pool
+            .check(
+                self.chain_id,
+                self.excluded_chain_ids,
+                self.max_lag_versions,
+            )
+            .await;
+        if health.iter().any(|(_, health)| health.is_healthy()) {
+            return Ok(health
+                .into_iter()
+                .filter(|(_, health)| !health.is_healthy())
+                .collect());
+        }
+
+        let reasons: Vec<_> = health
+            .iter()
+            .map(|(url, health)| format!("{} {}", url, health))
+            .collect();
+        bail!(
+            "No healthy node on the {} network: {}",
+            self.profile,
+            reasons.join("; ")
+        )
+    }
+}
+
+fn parse_url(url: &str, field: &str) -> Result<Url> {
+    Url::from_str(url).with_context(|| format!("Invalid value for {}: '{}'", field, url))
+}
@@ -0,0 +1,97 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::types::transaction::{RawTransaction, SignedTransaction};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::summary::Summary;
+
+// A transaction on its way through offline signing, as written between `build`, `sign` and
+// `submit`: hex encoded BCS of either the unsigned or the signed transaction, with a summary
+// for whoever reviews the file. The summary is checked against the transaction whenever the
+// file is read, so the one a reviewer saw is the one that gets signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionFile {
+    pub summary: Summary,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_transaction: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_transaction: Option<String>,
+}
+
+impl TransactionFile {
+    pub fn unsigned(raw: &RawTransaction) -> Result<Self> {
+        Ok(Self {
+            summary: Summary::of(raw)?,
+            raw_transaction: Some(encode(raw)?),
+            signed_transaction: None,
+        })
+    }
+
+    pub fn signed(signed: &SignedTransaction) -> Result<Self> {
+        Ok(Self {
+            summary: Summary::of(&signed.clone().into_raw_transaction())?,
+            raw_transaction: None,
+            signed_transaction: Some(encode(signed)?),
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read transaction file {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("{} is not a transaction file", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Could not write transaction file {}", path.display()))
+    }
+
+    pub fn raw_transaction(&self) -> Result<RawTransaction> {
+        let encoded = match &self.raw_transaction {
+            Some(encoded) => encoded,
+            None if self.signed_transaction.is_some() => {
+                bail!("The transaction file is already signed")
+            }
+            None => bail!("The transaction file holds no transaction"),
+        };
+        let raw: RawTransaction = decode(encoded)?;
+        self.check_summary(&raw)?;
+
+        Ok(raw)
+    }
+
+    pub fn signed_transaction(&self) -> Result<SignedTransaction> {
+        let encoded = match &self.signed_transaction {
+            Some(encoded) => encoded,
+            None if self.raw_transaction.is_some() => {
+                bail!("The transaction file has not been signed, run `sign` on it first")
+            }
+            None => bail!("The transaction file holds no transaction"),
+        };
+        let signed: SignedTransaction = decode(encoded)?;
+        self.check_summary(&signed.clone().into_raw_transaction())?;
+
+        Ok(signed)
+    }
+
+    fn check_summary(&self, raw: &RawTransaction) -> Result<()> {
+        if Summary::of(raw)? != self.summary {
+            bail!("The summary in the transaction file does not match its transaction");
+        }
+
+        Ok(())
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<String> {
+    Ok(format!("0x{}", hex::encode(bcs::to_bytes(value)?)))
+}
+
+fn decode<T: DeserializeOwned>(encoded: &str) -> Result<T> {
+    let bytes = hex::decode(encoded.trim().trim_start_matches("0x"))
+        .context("Transaction in the file is not valid hex")?;
+    bcs::from_bytes(&bytes).context("Transaction in the file is not valid BCS")
+}
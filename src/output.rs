@@ -0,0 +1,331 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::amount::Denomination;
+use crate::receipt::Receipt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => bail!("expected table, json, ndjson or csv, got '{}'", name),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Csv => "csv",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Balance,
+    Transfer,
+    Simulation,
+    Fund,
+    Create,
+    Wait,
+    Assertion,
+    Transaction,
+}
+
+impl fmt::Display for RecordKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RecordKind::Balance => "balance",
+            RecordKind::Transfer => "transfer",
+            RecordKind::Simulation => "simulation",
+            RecordKind::Fund => "fund",
+            RecordKind::Create => "create",
+            RecordKind::Wait => "wait",
+            RecordKind::Assertion => "assertion",
+            RecordKind::Transaction => "transaction",
+        };
+        f.write_str(name)
+    }
+}
+
+// One line of machine readable output. Balance records describe an account at some stage
+// of a command, transfer records a payment to `address`, simulation records a payment that
+// was only simulated, and transaction records any other committed transaction, labelled with
+// the function it called and addressed to its sender. Scenario steps with nothing to pay or
+// read get a record of their own kind, and anything that failed says why in `error`. The
+// field order is the CSV column order, and together the fields are the schema scripts rely on
+#[derive(Debug, Clone, Serialize)]
+pub struct Record {
+    pub record: RecordKind,
+    pub stage: Option<String>,
+    pub label: String,
+    pub address: String,
+    pub amount: u64,
+    pub tx_hash: Option<String>,
+    pub version: Option<u64>,
+    pub fee: Option<u64>,
+    pub error: Option<String>,
+}
+
+const CSV_HEADER: &str = "record,stage,label,address,amount,tx_hash,version,fee,error";
+
+impl Record {
+    pub fn new(record: RecordKind, label: &str, address: String, amount: u64) -> Self {
+        Self {
+            record,
+            stage: None,
+            label: label.to_string(),
+            address,
+            amount,
+            tx_hash: None,
+            version: None,
+            fee: None,
+            error: None,
+        }
+    }
+
+    pub fn balance(stage: Option<&str>, label: &str, address: String, amount: u64) -> Self {
+        Self {
+            stage: stage.map(str::to_string),
+            ..Self::new(RecordKind::Balance, label, address, amount)
+        }
+    }
+
+    // A transfer to `address`. Without a receipt it did not commit, so it has no hash,
+    // version or fee
+    pub fn transfer(label: &str, address: String, amount: u64, receipt: Option<&Receipt>) -> Self {
+        Self {
+            tx_hash: receipt.map(|receipt| receipt.hash.clone()),
+            version: receipt.map(|receipt| receipt.version),
+            fee: receipt.map(Receipt::fee),
+            ..Self::new(RecordKind::Transfer, label, address, amount)
+        }
+    }
+
+    // Any other committed transaction, with nothing paid that the record could show
+    pub fn transaction(label: &str, address: String, receipt: &Receipt) -> Self {
+        Self {
+            tx_hash: Some(receipt.hash.clone()),
+            version: Some(receipt.version),
+            fee: Some(receipt.fee()),
+            ..Self::new(RecordKind::Transaction, label, address, 0)
+        }
+    }
+
+    // A transfer the node only simulated, so there is no hash or version, just the predicted fee
+    pub fn simulation(label: &str, address: String, amount: u64, fee: u64) -> Self {
+        Self {
+            fee: Some(fee),
+            ..Self::new(RecordKind::Simulation, label, address, amount)
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            self.record.to_string(),
+            optional(self.stage.clone()),
+            self.label.clone(),
+            self.address.clone(),
+            self.amount.to_string(),
+            optional(self.tx_hash.clone()),
+            optional(self.version.map(|version| version.to_string())),
+            optional(self.fee.map(|fee| fee.to_string())),
+            optional(self.error.clone()),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+// Where command results go. Table output is for people and is printed by each command as
+// it sees fit; the structured formats get the same information as records, streamed line by
+// line for NDJSON and CSV, and collected into one array for JSON
+pub struct Output {
+    format: OutputFormat,
+    collected: RefCell<Vec<Record>>,
+    summary: RefCell<Option<serde_json::Value>>,
+    wrote_header: Cell<bool>,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            collected: RefCell::new(Vec::new()),
+            summary: RefCell::new(None),
+            wrote_header: Cell::new(false),
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub fn is_table(&self) -> bool {
+        self.format == OutputFormat::Table
+    }
+
+    // Emit a record in the structured formats. Table output ignores it, so callers print
+    // their own human readable version when `is_table` is set
+    pub fn emit(&self, record: Record) -> Result<()> {
+        match self.format {
+            OutputFormat::Table => {}
+            OutputFormat::Json => self.collected.borrow_mut().push(record),
+            OutputFormat::Ndjson => println!("{}", serde_json::to_string(&record)?),
+            OutputFormat::Csv => {
+                if !self.wrote_header.replace(true) {
+                    println!("{}", CSV_HEADER);
+                }
+                println!("{}", record.csv_row());
+            }
+        }
+
+        Ok(())
+    }
+
+    // A snapshot of balances, as a titled table or as balance records. Records always hold
+    // base units; the table shows them in the coin's denomination
+    pub fn balances(
+        &self,
+        title: Option<&str>,
+        denomination: &Denomination,
+        records: Vec<Record>,
+    ) -> Result<()> {
+        if !self.is_table() {
+            return records.into_iter().try_for_each(|record| self.emit(record));
+        }
+
+        if let Some(title) = title {
+            println!("\n===== {} =====", title);
+        }
+        let label_width = records
+            .iter()
+            .map(|record| record.label.len())
+            .max()
+            .unwrap_or(0);
+        let address_width = records
+            .iter()
+            .map(|record| record.address.len())
+            .max()
+            .unwrap_or(0);
+        for record in &records {
+            println!(
+                "{:label_width$}  {:address_width$}  {:>24}",
+                record.label,
+                record.address,
+                denomination.format(record.amount),
+                label_width = label_width,
+                address_width = address_width
+            );
+        }
+
+        Ok(())
+    }
+
+    // A committed transaction, as its full receipt or as its record
+    pub fn transfer(&self, record: Record, receipt: &Receipt) -> Result<()> {
+        match self.is_table() {
+            true => {
+                receipt.print();
+                Ok(())
+            }
+            false => self.emit(record),
+        }
+    }
+
+    // For commands that report one summary object rather than records. JSON output is then
+    // that object instead of an array, and NDJSON its single line
+    pub fn summary<T: Serialize>(&self, summary: &T) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                self.summary.replace(Some(serde_json::to_value(summary)?));
+            }
+            OutputFormat::Ndjson => println!("{}", serde_json::to_string(summary)?),
+            OutputFormat::Table | OutputFormat::Csv => {
+                bail!("A summary cannot be written as {} output", self.format)
+            }
+        }
+
+        Ok(())
+    }
+
+    // JSON output is a single document, so it can only be written once everything is in
+    pub fn finish(&self) -> Result<()> {
+        if self.format == OutputFormat::Json {
+            let document = match self.summary.take() {
+                Some(summary) => serde_json::to_string_pretty(&summary)?,
+                None => serde_json::to_string_pretty(&*self.collected.borrow())?,
+            };
+            println!("{}", document);
+        }
+
+        Ok(())
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains(&[',', '"', '\n'][..]) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+// Records rendered straight to the formats the golden files in tests/golden hold
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance() -> Record {
+        Record::balance(None, "0xa11ce", "0xa11ce".to_string(), 20_000)
+    }
+
+    #[test]
+    fn balance_json_matches_golden() {
+        let json = serde_json::to_string_pretty(&vec![balance()]).unwrap() + "\n";
+        assert_eq!(json, include_str!("../tests/golden/balance.json"));
+    }
+
+    #[test]
+    fn balance_ndjson_matches_golden() {
+        let ndjson = serde_json::to_string(&balance()).unwrap() + "\n";
+        assert_eq!(ndjson, include_str!("../tests/golden/balance.ndjson"));
+    }
+
+    #[test]
+    fn balance_csv_matches_golden() {
+        let csv = format!("{}\n{}\n", CSV_HEADER, balance().csv_row());
+        assert_eq!(csv, include_str!("../tests/golden/balance.csv"));
+    }
+
+    #[test]
+    fn csv_fields_with_commas_and_quotes_are_quoted() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
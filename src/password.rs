@@ -0,0 +1,63 @@
+use anyhow::{bail, Context, Result};
+use once_cell::sync::OnceCell;
+use std::fs;
+use std::path::PathBuf;
+
+// Where a keystore password comes from: a file, an environment variable, or an interactive
+// prompt, in that order. The value is read at most once per run
+pub struct Password {
+    file: Option<PathBuf>,
+    env_var: &'static str,
+    cached: OnceCell<String>,
+}
+
+impl Password {
+    pub fn new(file: Option<PathBuf>, env_var: &'static str) -> Self {
+        Self {
+            file,
+            env_var,
+            cached: OnceCell::new(),
+        }
+    }
+
+    // Password for an existing key
+    pub fn get(&self, prompt: &str) -> Result<&str> {
+        self.read(prompt, false)
+    }
+
+    // Password for a key about to be encrypted; interactive input has to be typed twice
+    pub fn get_new(&self, prompt: &str) -> Result<&str> {
+        self.read(prompt, true)
+    }
+
+    fn read(&self, prompt: &str, confirm: bool) -> Result<&str> {
+        self.cached
+            .get_or_try_init(|| {
+                if let Some(file) = &self.file {
+                    let contents = fs::read_to_string(file).with_context(|| {
+                        format!("Could not read password file {}", file.display())
+                    })?;
+                    return Ok(contents.trim_end_matches(&['\r', '\n'][..]).to_string());
+                }
+                if let Ok(password) = std::env::var(self.env_var) {
+                    return Ok(password);
+                }
+
+                let password =
+                    rpassword::prompt_password(prompt).context("Could not read password")?;
+                if confirm {
+                    let repeated = rpassword::prompt_password("Repeat password: ")
+                        .context("Could not read password")?;
+                    if password != repeated {
+                        bail!("Passwords do not match");
+                    }
+                }
+                if password.is_empty() {
+                    bail!("Password must not be empty");
+                }
+
+                Ok(password)
+            })
+            .map(|password| password.as_str())
+    }
+}
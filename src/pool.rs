@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use aptos_sdk::rest_client::{Client, FaucetClient};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+use crate::retry::RetryPolicy;
+
+// How long a node has to answer its health check before it counts as unreachable
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The fullnodes of one network, in order of preference. Requests go to the active node, and
+// one that fails transiently moves the pool on to the next healthy node before it is retried.
+// Clones share which node is active
+#[derive(Clone)]
+pub struct NodePool {
+    nodes: Arc<Vec<Node>>,
+    active: Arc<AtomicUsize>,
+}
+
+struct Node {
+    url: Url,
+    client: Client,
+    healthy: AtomicBool,
+}
+
+// What a node's ledger info said about it when the pool was checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Health {
+    Healthy { version: u64 },
+    Unreachable { error: String },
+    WrongChain { chain_id: u8, expected: u8 },
+    ExcludedChain { chain_id: u8 },
+    Lagging { version: u64, behind: u64 },
+}
+
+impl Health {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Health::Healthy { .. })
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Health::Healthy { version } => write!(f, "healthy at version {}", version),
+            Health::Unreachable { error } => write!(f, "unreachable: {}", error),
+            Health::WrongChain { chain_id, expected } => write!(
+                f,
+                "reports chain id {} but chain id {} is expected",
+                chain_id, expected
+            ),
+            Health::ExcludedChain { chain_id } => write!(
+                f,
+                "reports chain id {}, which belongs to another network",
+                chain_id
+            ),
+            Health::Lagging { version, behind } => write!(
+                f,
+                "at version {}, {} versions behind the newest node",
+                version, behind
+            ),
+        }
+    }
+}
+
+impl NodePool {
+    // Every node starts out healthy and the first one active, until `check` says otherwise
+    pub fn new(urls: Vec<Url>) -> Self {
+        assert!(!urls.is_empty(), "a node pool needs at least one node");
+        let nodes = urls
+            .into_iter()
+            .map(|url| Node {
+                client: Client::new(url.clone()),
+                url,
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+
+        Self {
+            nodes: Arc::new(nodes),
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    // The node requests go to now
+    pub fn url(&self) -> Url {
+        self.nodes[self.active.load(Ordering::SeqCst)].url.clone()
+    }
+
+    // A client for the active node alone. Calls made through it do not fail over, so the app
+    // makes its calls through `run` instead
+    pub fn client(&self) -> Client {
+        self.nodes[self.active.load(Ordering::SeqCst)]
+            .client
+            .clone()
+    }
+
+    // The faucet waits for its mint transactions on the active node
+    pub fn faucet_client(&self, faucet_url: &Url) -> FaucetClient {
+        FaucetClient::new(faucet_url.clone(), self.url())
+    }
+
+    // Ask every node for its ledger info at once. A node is healthy if it answers, is on
+    // `chain_id` when one is expected and otherwise on none of `excluded` and on the same chain
+    // as the first node that answered, and is no more than `max_lag` versions behind the
+    // newest node on that chain. The first healthy node becomes the active one
+    pub async fn check(
+        &self,
+        chain_id: Option<u8>,
+        excluded: &[u8],
+        max_lag: u64,
+    ) -> Vec<(Url, Health)> {
+        let handles: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                tokio::spawn(ledger_version(
+                    node.client.clone(),
+                    chain_id,
+                    excluded.to_vec(),
+                ))
+            })
+            .collect();
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(match handle.await {
+                Ok(result) => result,
+                Err(err) => Err(Health::Unreachable {
+                    error: err.to_string(),
+                }),
+            });
+        }
+
+        // Without a pinned chain id the pool still has to be on one chain, or requests would
+        // land on whichever one the active node happens to be on
+        let expected = chain_id.or_else(|| {
+            results
+                .iter()
+                .find_map(|result| result.as_ref().ok().map(|(chain_id, _)| *chain_id))
+        });
+        let newest = results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .filter(|(chain_id, _)| Some(*chain_id) == expected)
+            .map(|(_, version)| *version)
+            .max()
+            .unwrap_or(0);
+        let health: Vec<_> = results
+            .into_iter()
+            .map(|result| match (result, expected) {
+                (Ok((chain_id, _)), Some(expected)) if chain_id != expected => {
+                    Health::WrongChain { chain_id, expected }
+                }
+                (Ok((_, version)), _) if newest - version > max_lag => Health::Lagging {
+                    version,
+                    behind: newest - version,
+                },
+                (Ok((_, version)), _) => Health::Healthy { version },
+                (Err(health), _) => health,
+            })
+            .collect();
+
+        for (node, health) in self.nodes.iter().zip(&health) {
+            node.healthy.store(health.is_healthy(), Ordering::SeqCst);
+        }
+        if let Some(first) = health.iter().position(Health::is_healthy) {
+            self.active.store(first, Ordering::SeqCst);
+        }
+
+        self.nodes
+            .iter()
+            .map(|node| node.url.clone())
+            .zip(health)
+            .collect()
+    }
+
+    // Call `call` with the active node's client. A transient failure moves on to the next
+    // healthy node at once, and only once every healthy node has failed does it count as an
+    // attempt under `retry` and wait out its backoff before going round again
+    pub async fn run<T, F, Fut>(&self, retry: &RetryPolicy, mut call: F) -> Result<T>
+    where
+        F: FnMut(Client) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        let mut failed_nodes = 0;
+        loop {
+            let index = self.active.load(Ordering::SeqCst);
+            let err = match call(self.nodes[index].client.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            if !retry.is_retryable(&err) {
+                return Err(err);
+            }
+
+            self.fail_over(index);
+            failed_nodes += 1;
+            if failed_nodes < self.healthy_count() {
+                continue;
+            }
+            failed_nodes = 0;
+            match retry.delay(attempt, &err) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(err),
+            }
+            attempt += 1;
+        }
+    }
+
+    fn healthy_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| node.healthy.load(Ordering::SeqCst))
+            .count()
+    }
+
+    // Make the healthy node after `failed` the active one, unless another request already
+    // moved the pool on from it
+    fn fail_over(&self, failed: usize) {
+        let count = self.nodes.len();
+        let next = (1..count)
+            .map(|offset| (failed + offset) % count)
+            .find(|&index| self.nodes[index].healthy.load(Ordering::SeqCst));
+        if let Some(next) = next {
+            let _ = self
+                .active
+                .compare_exchange(failed, next, Ordering::SeqCst, Ordering::SeqCst);
+        }
+    }
+}
+
+// The node's chain id and ledger version, or why it cannot be used
+async fn ledger_version(
+    client: Client,
+    chain_id: Option<u8>,
+    excluded: Vec<u8>,
+) -> Result<(u8, u64), Health> {
+    let info = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, client.get_ledger_information())
+        .await
+        .context("Timed out fetching ledger info")
+        .and_then(|result| result.context("Could not fetch ledger info"))
+        .map_err(|err| Health::Unreachable {
+            error: format!("{:#}", err),
+        })?
+        .into_inner();
+
+    match chain_id {
+        Some(expected) if info.chain_id != expected => Err(Health::WrongChain {
+            chain_id: info.chain_id,
+            expected,
+        }),
+        None if excluded.contains(&info.chain_id) => Err(Health::ExcludedChain {
+            chain_id: info.chain_id,
+        }),
+        _ => Ok((info.chain_id, info.version)),
+    }
+}
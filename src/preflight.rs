@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::types::account_address::AccountAddress;
+use thiserror::Error;
+
+use crate::account;
+use crate::coin;
+use crate::config::APTOS_COIN_TYPE;
+use crate::error::ClientError;
+use crate::pool::NodePool;
+use crate::retry::RetryPolicy;
+
+// Why a transfer would fail, found before it is signed so it costs neither gas nor a sequence
+// number. Amounts are in the coin's smallest unit
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PreflightError {
+    #[error("sender {0} has no onchain account")]
+    SenderNotFound(String),
+    #[error(
+        "sender {address} holds {balance} octas of APT but needs {amount} to send and up to {fee} for gas"
+    )]
+    InsufficientApt {
+        address: String,
+        balance: u64,
+        amount: u64,
+        fee: u64,
+    },
+    #[error("sender {address} holds {balance} octas of APT but gas may cost up to {fee}")]
+    InsufficientGas {
+        address: String,
+        balance: u64,
+        fee: u64,
+    },
+    #[error("sender {address} holds {balance} of {coin_type} but is sending {amount}")]
+    InsufficientCoins {
+        address: String,
+        coin_type: String,
+        balance: u64,
+        amount: u64,
+    },
+    #[error("recipient {0} has no onchain account, pass --auto-create to create it")]
+    RecipientNotFound(String),
+    #[error("recipient {address} has not registered a CoinStore for {coin_type}")]
+    CoinStoreNotRegistered { address: String, coin_type: String },
+}
+
+impl PreflightError {
+    // The category the failure exits with
+    pub fn category(&self) -> ClientError {
+        match self {
+            PreflightError::SenderNotFound(_)
+            | PreflightError::RecipientNotFound(_)
+            | PreflightError::CoinStoreNotRegistered { .. } => {
+                ClientError::AccountNotFound(self.to_string())
+            }
+            PreflightError::InsufficientApt { .. }
+            | PreflightError::InsufficientGas { .. }
+            | PreflightError::InsufficientCoins { .. } => {
+                ClientError::InsufficientBalance(self.to_string())
+            }
+        }
+    }
+}
+
+// What a passed check did on the way, for the command to report
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    // The recipient had no account, so `auto_create` made one
+    pub created_recipient: bool,
+}
+
+// Check that a transfer can go through before it is signed: the sender exists and can pay the
+// amount plus the most its gas could cost (max_gas_amount x gas_unit_price), and the recipient
+// exists and, for coins other than APT, has registered a CoinStore for the coin. Creating an
+// account registers APT's. A missing recipient is created through `auto_create` if given.
+// Transfer and batch check every transfer they send. Scenarios and benches go without, as
+// they send between accounts they created and funded themselves, and a bench would time the
+// checks along with the transfers
+pub async fn check(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    sender: AccountAddress,
+    recipient: AccountAddress,
+    amount: u64,
+    options: &TransferOptions<'_>,
+    auto_create: Option<&FaucetClient>,
+) -> Result<Report> {
+    let mut report = Report::default();
+    let coin_type = options.coin_type;
+    let fee = options
+        .max_gas_amount
+        .saturating_mul(options.gas_unit_price);
+
+    if account::get(nodes, retry, sender).await?.is_none() {
+        return Err(PreflightError::SenderNotFound(sender.to_hex_literal()).into());
+    }
+    let apt = coin::balance(nodes, retry, sender, APTOS_COIN_TYPE)
+        .await
+        .context("Could not fetch sender's balance")?
+        .unwrap_or(0);
+    if coin_type == APTOS_COIN_TYPE {
+        if apt < amount.saturating_add(fee) {
+            return Err(PreflightError::InsufficientApt {
+                address: sender.to_hex_literal(),
+                balance: apt,
+                amount,
+                fee,
+            }
+            .into());
+        }
+    } else {
+        if apt < fee {
+            return Err(PreflightError::InsufficientGas {
+                address: sender.to_hex_literal(),
+                balance: apt,
+                fee,
+            }
+            .into());
+        }
+        let balance = coin::balance(nodes, retry, sender, coin_type)
+            .await
+            .context("Could not fetch sender's balance")?
+            .unwrap_or(0);
+        if balance < amount {
+            return Err(PreflightError::InsufficientCoins {
+                address: sender.to_hex_literal(),
+                coin_type: coin_type.to_string(),
+                balance,
+                amount,
+            }
+            .into());
+        }
+    }
+
+    if account::get(nodes, retry, recipient).await?.is_none() {
+        let faucet_client = auto_create
+            .ok_or_else(|| PreflightError::RecipientNotFound(recipient.to_hex_literal()))?;
+        retry
+            .run(|| faucet_client.create_account(recipient))
+            .await
+            .map_err(ClientError::faucet)
+            .with_context(|| {
+                format!(
+                    "Failed to create onchain account for {}",
+                    recipient.to_hex_literal()
+                )
+            })?;
+        report.created_recipient = true;
+    }
+    if coin_type != APTOS_COIN_TYPE
+        && coin::balance(nodes, retry, recipient, coin_type)
+            .await
+            .context("Could not fetch recipient's balance")?
+            .is_none()
+    {
+        return Err(PreflightError::CoinStoreNotRegistered {
+            address: recipient.to_hex_literal(),
+            coin_type: coin_type.to_string(),
+        }
+        .into());
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use aptos_sdk::rest_client::aptos_api_types::Transaction;
+use aptos_sdk::rest_client::PendingTransaction;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::amount::Denomination;
+use crate::api_json::{self, parse_u64};
+use crate::pool::NodePool;
+use crate::retry::RetryPolicy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoinEventKind {
+    Withdraw,
+    Deposit,
+}
+
+// A coin moving in or out of an account's CoinStore
+#[derive(Debug, Clone)]
+pub struct CoinEvent {
+    pub kind: CoinEventKind,
+    pub address: String,
+    pub amount: u64,
+}
+
+// What a committed transaction did and what it cost
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub hash: String,
+    pub version: u64,
+    pub success: bool,
+    pub vm_status: String,
+    pub gas_used: u64,
+    pub gas_unit_price: u64,
+    pub events: Vec<CoinEvent>,
+}
+
+impl Receipt {
+    // Read the receipt out of the transaction returned by `wait_for_transaction`
+    pub fn from_transaction(transaction: &Transaction) -> Result<Self> {
+        let json = api_json::transaction(transaction)?;
+        let (success, vm_status) = api_json::execution_status(&json);
+
+        let events = json["events"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(coin_event)
+            .collect();
+
+        Ok(Self {
+            hash: json["hash"]
+                .as_str()
+                .context("Transaction has no hash")?
+                .to_string(),
+            version: api_json::u64_field(&json, "version", "Transaction")?,
+            success,
+            vm_status,
+            gas_used: api_json::u64_field(&json, "gas_used", "Transaction")?,
+            // Only user transactions pay for gas
+            gas_unit_price: parse_u64(&json["gas_unit_price"]).unwrap_or(0),
+            events,
+        })
+    }
+
+    pub fn fee(&self) -> u64 {
+        self.gas_used * self.gas_unit_price
+    }
+
+    pub fn print(&self) {
+        println!("Transaction: {}", self.hash);
+        println!("Version: {}", self.version);
+        println!("VM status: {}", self.vm_status);
+        println!("Gas used: {}", self.gas_used);
+        println!("Gas unit price: {}", self.gas_unit_price);
+        println!(
+            "Fee: {} octas ({})",
+            self.fee(),
+            Denomination::apt().format(self.fee())
+        );
+        for event in &self.events {
+            let kind = match event.kind {
+                CoinEventKind::Withdraw => "Withdraw",
+                CoinEventKind::Deposit => "Deposit",
+            };
+            println!("{} {}: {}", kind, event.address, event.amount);
+        }
+    }
+}
+
+// Wait for a submitted transaction, giving up after `timeout`, and read its receipt. A node
+// that stops answering mid-wait hands the wait on to the next one
+pub async fn wait(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    pending: &PendingTransaction,
+    timeout: Duration,
+) -> Result<Receipt> {
+    let waiting = nodes.run(retry, |client| async move {
+        Ok(client.wait_for_transaction(pending).await?)
+    });
+    let transaction = tokio::time::timeout(timeout, waiting)
+        .await
+        .with_context(|| {
+            format!(
+                "Transaction {} was not committed within {}s",
+                pending.hash,
+                timeout.as_secs()
+            )
+        })?
+        .context("Failed to wait for transaction")?;
+
+    Receipt::from_transaction(transaction.inner())
+}
+
+// Withdraw and deposit events of any coin type. Other events are ignored
+fn coin_event(event: &Value) -> Option<CoinEvent> {
+    let kind = match event["type"].as_str()? {
+        "0x1::coin::WithdrawEvent" => CoinEventKind::Withdraw,
+        "0x1::coin::DepositEvent" => CoinEventKind::Deposit,
+        _ => return None,
+    };
+
+    Some(CoinEvent {
+        kind,
+        address: event_address(event)?,
+        amount: parse_u64(&event["data"]["amount"])?,
+    })
+}
+
+// The account an event belongs to. Newer nodes spell out the event handle's GUID, older ones
+// only send its key: an 8 byte creation number followed by the account address
+fn event_address(event: &Value) -> Option<String> {
+    if let Some(address) = event["guid"]["account_address"].as_str() {
+        return Some(address.to_string());
+    }
+
+    let key = event["key"].as_str()?.trim_start_matches("0x");
+    Some(format!("0x{}", key.get(16..)?))
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+pub const DEFAULT_RETRYABLE_STATUS_CODES: [u16; 5] = [429, 500, 502, 503, 504];
+
+// Where the SDK's errors mention the HTTP status a node or faucet answered with: the rest
+// client's `HTTP error 503 ...` and `status_code: 503`, the `code` of an API error body as
+// `AptosError { code: 503, ...`, and reqwest's `HTTP status server error (503 ...` for the
+// faucet. Each is matched whole, so numbers after a bare `code: ` elsewhere are not statuses
+const STATUS_PREFIXES: [&str; 5] = [
+    "HTTP error ",
+    "status_code: ",
+    "AptosError { code: ",
+    "HTTP status client error (",
+    "HTTP status server error (",
+];
+
+// Requests that never got an answer, which are always worth another try
+const TRANSPORT_ERRORS: [&str; 4] = [
+    "error sending request",
+    "error trying to connect",
+    "connection closed before message completed",
+    "operation timed out",
+];
+
+// How calls to the node and faucet are retried when they fail transiently: the server was
+// unreachable or answered with one of `retryable_status_codes`. Anything else fails at once
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    // Tries in all, so 1 never retries
+    pub max_attempts: u32,
+    // Wait before the first retry, doubling for each one after it up to `max_backoff`
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // Each wait is moved up or down at random by up to this fraction of itself, so clients
+    // that failed together do not all retry together
+    pub jitter: f64,
+    pub retryable_status_codes: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.2,
+            retryable_status_codes: DEFAULT_RETRYABLE_STATUS_CODES.to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    // Call `call` until it succeeds, fails for good or runs out of attempts, returning the
+    // last error
+    pub async fn run<T, F, Fut>(&self, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let err = match call().await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            match self.delay(attempt, &err) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(err),
+            }
+            attempt += 1;
+        }
+    }
+
+    // How long to wait after `attempt` tries ended in `err`, or None to give up
+    pub fn delay(&self, attempt: u32, err: &anyhow::Error) -> Option<Duration> {
+        match attempt < self.max_attempts && self.is_retryable(err) {
+            true => Some(self.backoff(attempt)),
+            false => None,
+        }
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(32) as i32;
+        let backoff = (self.initial_backoff.as_secs_f64() * 2f64.powi(doublings))
+            .min(self.max_backoff.as_secs_f64());
+        let jitter = match self.jitter > 0.0 {
+            true => rand::thread_rng().gen_range(-self.jitter, self.jitter),
+            false => 0.0,
+        };
+
+        Duration::from_secs_f64(backoff * (1.0 + jitter))
+    }
+
+    // The SDK reports statuses and transport failures only in its error messages, so that is
+    // where they are looked for, in every error of the chain
+    pub fn is_retryable(&self, err: &anyhow::Error) -> bool {
+        err.chain().any(|cause| {
+            let messages = [cause.to_string(), format!("{:?}", cause)];
+            messages.iter().any(|message| {
+                TRANSPORT_ERRORS
+                    .iter()
+                    .any(|transport| message.contains(transport))
+                    || status_codes(message)
+                        .any(|status| self.retryable_status_codes.contains(&status))
+            })
+        })
+    }
+}
+
+// Every three digit number following one of the status prefixes
+fn status_codes(message: &str) -> impl Iterator<Item = u16> + '_ {
+    STATUS_PREFIXES.iter().flat_map(move |prefix| {
+        message.match_indices(prefix).filter_map(move |(index, _)| {
+            let digits = message.get(index + prefix.len()..)?.get(..3)?;
+            match digits.bytes().all(|byte| byte.is_ascii_digit()) {
+                true => digits.parse().ok(),
+                false => None,
+            }
+        })
+    })
+}
@@ -0,0 +1,401 @@
+use anyhow::{bail, Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::coin;
+use crate::conservation::{Balances, Conservation};
+use crate::error::ClientError;
+use crate::pool::NodePool;
+use crate::receipt::{self, Receipt};
+use crate::retry::RetryPolicy;
+use crate::transaction;
+
+// One thing to do in a scenario. Amounts are in base units (octas for APT)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    // Mint APT to an account through the faucet, creating it if needed
+    Fund {
+        account: String,
+        amount: u64,
+    },
+    // Create an account onchain through the faucet, without coins
+    Create {
+        account: String,
+    },
+    Transfer {
+        from: String,
+        to: String,
+        amount: u64,
+        settings: TransferSettings,
+    },
+    Wait {
+        duration: Duration,
+    },
+    // The account's balance of `coin_type`, or of the runner's coin, must be within
+    // `tolerance` of `amount` either way, leaving room for gas costs not known up front
+    AssertBalance {
+        account: String,
+        amount: u64,
+        tolerance: u64,
+        coin_type: Option<String>,
+    },
+    // Read every account's balance, labelled with a stage such as "Initial"
+    Snapshot {
+        stage: String,
+    },
+}
+
+// Settings for one transfer that replace the runner's transfer options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferSettings {
+    pub coin_type: Option<String>,
+    pub max_gas_amount: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    pub expiration_secs: Option<u64>,
+}
+
+impl Step {
+    fn accounts(&self) -> Vec<&str> {
+        match self {
+            Step::Fund { account, .. }
+            | Step::Create { account }
+            | Step::AssertBalance { account, .. } => vec![account.as_str()],
+            Step::Transfer { from, to, .. } => vec![from.as_str(), to.as_str()],
+            Step::Wait { .. } | Step::Snapshot { .. } => Vec::new(),
+        }
+    }
+}
+
+// A set of named accounts and the steps to run against them, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub name: String,
+    pub accounts: Vec<String>,
+    pub steps: Vec<Step>,
+}
+
+impl Scenario {
+    // Every account a step names has to be declared, and only once
+    pub fn validate(&self) -> Result<()> {
+        for (index, name) in self.accounts.iter().enumerate() {
+            if self.accounts[..index].contains(name) {
+                bail!("Scenario '{}' declares account '{}' twice", self.name, name);
+            }
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            for account in step.accounts() {
+                if !self.accounts.iter().any(|name| name == account) {
+                    bail!(
+                        "Step {} of scenario '{}' uses undeclared account '{}'",
+                        index + 1,
+                        self.name,
+                        account
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Balance {
+    pub account: String,
+    pub address: AccountAddress,
+    pub amount: u64,
+}
+
+// What a step did
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    Funded,
+    Created,
+    // Committed, though the receipt says whether it succeeded, and whether the balances
+    // around it moved by exactly the amount and fee
+    Transferred {
+        receipt: Receipt,
+        conservation: Conservation,
+    },
+    Waited,
+    Balance {
+        expected: u64,
+        actual: u64,
+        tolerance: u64,
+    },
+    Snapshot {
+        balances: Vec<Balance>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        match self {
+            Outcome::Transferred {
+                receipt,
+                conservation,
+            } => receipt.success && conservation.holds(),
+            Outcome::Balance {
+                expected,
+                actual,
+                tolerance,
+            } => expected.abs_diff(*actual) <= *tolerance,
+            Outcome::Failed { .. } => false,
+            Outcome::Funded | Outcome::Created | Outcome::Waited | Outcome::Snapshot { .. } => true,
+        }
+    }
+
+    // Why the step did not pass, in base units
+    pub fn failure(&self) -> Option<String> {
+        match self {
+            Outcome::Transferred {
+                receipt,
+                conservation,
+            } => match (receipt.success, conservation.holds()) {
+                (true, true) => None,
+                (false, _) => Some(receipt.vm_status.clone()),
+                (true, false) => Some(format!("Transfer {} left {}", receipt.hash, conservation)),
+            },
+            Outcome::Balance {
+                expected,
+                tolerance,
+                ..
+            } if !self.passed() => {
+                Some(format!("expected {} give or take {}", expected, tolerance))
+            }
+            Outcome::Failed { error } => Some(error.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub step: Step,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub scenario: String,
+    pub accounts: Vec<(String, AccountAddress)>,
+    pub steps: Vec<StepReport>,
+}
+
+impl Report {
+    // Whether every step ran and passed. A run stops at the first step that fails outright,
+    // so a report can be shorter than its scenario
+    pub fn passed(&self, scenario: &Scenario) -> bool {
+        self.steps.len() == scenario.steps.len()
+            && self.steps.iter().all(|step| step.outcome.passed())
+    }
+}
+
+// Runs scenarios against one network's nodes and faucet
+pub struct Runner<'a> {
+    pub nodes: &'a NodePool,
+    pub faucet_client: Option<FaucetClient>,
+    pub options: TransferOptions<'a>,
+    pub wait_timeout: Duration,
+    pub retry: RetryPolicy,
+}
+
+impl<'a> Runner<'a> {
+    // Run every step in order. `accounts` supplies keys for any of the scenario's accounts,
+    // and gets a fresh key for each one it lacks; sequence numbers in it are kept up to date,
+    // so callers can save them afterwards. Balance mismatches are reported and the run goes
+    // on, while a step that errors ends it
+    pub async fn run(
+        &self,
+        scenario: &Scenario,
+        accounts: &mut HashMap<String, LocalAccount>,
+    ) -> Result<Report> {
+        scenario.validate()?;
+        for name in &scenario.accounts {
+            accounts
+                .entry(name.clone())
+                .or_insert_with(|| LocalAccount::generate(&mut rand::rngs::OsRng));
+        }
+
+        let mut report = Report {
+            scenario: scenario.name.clone(),
+            accounts: scenario
+                .accounts
+                .iter()
+                .map(|name| (name.clone(), accounts[name].address()))
+                .collect(),
+            steps: Vec::with_capacity(scenario.steps.len()),
+        };
+        for step in &scenario.steps {
+            let outcome = match self.step(step, &report.accounts, accounts).await {
+                Ok(outcome) => outcome,
+                Err(err) => Outcome::Failed {
+                    error: format!("{:#}", err),
+                },
+            };
+            let failed = matches!(outcome, Outcome::Failed { .. });
+            report.steps.push(StepReport {
+                step: step.clone(),
+                outcome,
+            });
+            if failed {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn step(
+        &self,
+        step: &Step,
+        addresses: &[(String, AccountAddress)],
+        accounts: &mut HashMap<String, LocalAccount>,
+    ) -> Result<Outcome> {
+        match step {
+            Step::Fund { account, amount } => {
+                let faucet_client = self.faucet()?;
+                let address = accounts[account].address();
+                self.retry
+                    .run(|| faucet_client.fund(address, *amount))
+                    .await
+                    .map_err(ClientError::faucet)
+                    .with_context(|| format!("Failed to fund {}", account))?;
+                Ok(Outcome::Funded)
+            }
+            Step::Create { account } => {
+                let faucet_client = self.faucet()?;
+                let address = accounts[account].address();
+                self.retry
+                    .run(|| faucet_client.create_account(address))
+                    .await
+                    .map_err(ClientError::faucet)
+                    .with_context(|| format!("Failed to create onchain account for {}", account))?;
+                Ok(Outcome::Created)
+            }
+            Step::Transfer {
+                from,
+                to,
+                amount,
+                settings,
+            } => {
+                let options = TransferOptions {
+                    coin_type: settings
+                        .coin_type
+                        .as_deref()
+                        .unwrap_or(self.options.coin_type),
+                    max_gas_amount: settings
+                        .max_gas_amount
+                        .unwrap_or(self.options.max_gas_amount),
+                    gas_unit_price: settings
+                        .gas_unit_price
+                        .unwrap_or(self.options.gas_unit_price),
+                    timeout_secs: settings
+                        .expiration_secs
+                        .unwrap_or(self.options.timeout_secs),
+                };
+                let recipient = accounts[to].address();
+                let sender = accounts.get_mut(from).unwrap();
+                let before = Balances::fetch(
+                    self.nodes,
+                    &self.retry,
+                    sender.address(),
+                    recipient,
+                    options.coin_type,
+                )
+                .await?;
+                let pending = transaction::transfer(
+                    self.nodes,
+                    &self.retry,
+                    sender,
+                    recipient,
+                    *amount,
+                    &options,
+                )
+                .await
+                .with_context(|| format!("Failed to transfer coins from {} to {}", from, to))?;
+                let receipt =
+                    receipt::wait(self.nodes, &self.retry, &pending, self.wait_timeout).await?;
+                let after = Balances::fetch(
+                    self.nodes,
+                    &self.retry,
+                    sender.address(),
+                    recipient,
+                    options.coin_type,
+                )
+                .await?;
+                let conservation = Conservation::new(
+                    before,
+                    after,
+                    *amount,
+                    &receipt,
+                    sender.address(),
+                    recipient,
+                    options.coin_type,
+                );
+                Ok(Outcome::Transferred {
+                    receipt,
+                    conservation,
+                })
+            }
+            Step::Wait { duration } => {
+                tokio::time::sleep(*duration).await;
+                Ok(Outcome::Waited)
+            }
+            Step::AssertBalance {
+                account,
+                amount,
+                tolerance,
+                coin_type,
+            } => {
+                let coin_type = coin_type.as_deref().unwrap_or(self.options.coin_type);
+                Ok(Outcome::Balance {
+                    expected: *amount,
+                    actual: self
+                        .balance(account, accounts[account].address(), coin_type)
+                        .await?,
+                    tolerance: *tolerance,
+                })
+            }
+            Step::Snapshot { .. } => {
+                let mut balances = Vec::with_capacity(addresses.len());
+                for (account, address) in addresses {
+                    balances.push(Balance {
+                        account: account.clone(),
+                        address: *address,
+                        amount: self
+                            .balance(account, *address, self.options.coin_type)
+                            .await?,
+                    });
+                }
+                Ok(Outcome::Snapshot { balances })
+            }
+        }
+    }
+
+    fn faucet(&self) -> Result<&FaucetClient> {
+        self.faucet_client
+            .as_ref()
+            .context("This scenario needs a faucet, set faucet_url")
+    }
+
+    // Accounts without a CoinStore hold nothing
+    async fn balance(
+        &self,
+        account: &str,
+        address: AccountAddress,
+        coin_type: &str,
+    ) -> Result<u64> {
+        Ok(coin::balance(self.nodes, &self.retry, address, coin_type)
+            .await
+            .with_context(|| format!("Could not fetch {}'s balance", account))?
+            .unwrap_or(0))
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::amount::{self, Denomination};
+use crate::scenario::{Scenario, Step, TransferSettings};
+
+// A scenario as written in YAML:
+//
+//     name: pay bob
+//     accounts: [alice, bob]
+//     steps:
+//       - fund: { account: alice, amount: 1 APT }
+//       - create: { account: bob }
+//       - transfer: { from: alice, to: bob, amount: 0.25 APT, gas_unit_price: 100 }
+//       - wait: { secs: 2 }
+//       - assert_balance: { account: alice, amount: 0.75 APT, tolerance: 0.001 APT }
+//
+// Amounts are kept as text until `resolve`, since how they parse depends on their coin
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioFile {
+    pub name: Option<String>,
+    pub accounts: Vec<String>,
+    pub steps: Vec<FileStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum FileStep {
+    Fund {
+        account: String,
+        #[serde(deserialize_with = "amount::deserialize_text")]
+        amount: String,
+    },
+    Create {
+        account: String,
+    },
+    Transfer {
+        from: String,
+        to: String,
+        #[serde(deserialize_with = "amount::deserialize_text")]
+        amount: String,
+        coin_type: Option<String>,
+        max_gas_amount: Option<u64>,
+        gas_unit_price: Option<u64>,
+        expiration_secs: Option<u64>,
+    },
+    Wait {
+        secs: u64,
+    },
+    AssertBalance {
+        account: String,
+        #[serde(deserialize_with = "amount::deserialize_text")]
+        amount: String,
+        #[serde(
+            default = "no_tolerance",
+            deserialize_with = "amount::deserialize_text"
+        )]
+        tolerance: String,
+        coin_type: Option<String>,
+    },
+    Snapshot {
+        stage: String,
+    },
+}
+
+fn no_tolerance() -> String {
+    "0".to_string()
+}
+
+impl ScenarioFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read scenario file {}", path.display()))?;
+        serde_yaml::from_str(&contents)
+            .with_context(|| format!("Invalid scenario file {}", path.display()))
+    }
+
+    // Every coin whose amounts appear in the file, where steps without a coin type use
+    // `default_coin`. Funding is always in APT
+    pub fn coin_types(&self, default_coin: &str) -> Vec<String> {
+        let mut coin_types = Vec::new();
+        for step in &self.steps {
+            let coin_type = match step {
+                FileStep::Transfer { coin_type, .. }
+                | FileStep::AssertBalance { coin_type, .. } => {
+                    coin_type.as_deref().unwrap_or(default_coin)
+                }
+                _ => continue,
+            };
+            if !coin_types.iter().any(|known| known == coin_type) {
+                coin_types.push(coin_type.to_string());
+            }
+        }
+
+        coin_types
+    }
+
+    // Parse every amount in its coin's denomination, which `denominations` must hold for
+    // each of `coin_types`. Unnamed scenarios are named after their file
+    pub fn resolve(
+        self,
+        path: &Path,
+        denominations: &HashMap<String, Denomination>,
+        default_coin: &str,
+    ) -> Result<Scenario> {
+        let denomination = |coin_type: &Option<String>| {
+            &denominations[coin_type.as_deref().unwrap_or(default_coin)]
+        };
+
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for (index, step) in self.steps.into_iter().enumerate() {
+            let invalid = || format!("Step {} has an invalid amount", index + 1);
+            steps.push(match step {
+                FileStep::Fund { account, amount } => Step::Fund {
+                    account,
+                    amount: Denomination::apt().parse(&amount).with_context(invalid)?,
+                },
+                FileStep::Create { account } => Step::Create { account },
+                FileStep::Transfer {
+                    from,
+                    to,
+                    amount,
+                    coin_type,
+                    max_gas_amount,
+                    gas_unit_price,
+                    expiration_secs,
+                } => Step::Transfer {
+                    from,
+                    to,
+                    amount: denomination(&coin_type)
+                        .parse(&amount)
+                        .with_context(invalid)?,
+                    settings: TransferSettings {
+                        coin_type,
+                        max_gas_amount,
+                        gas_unit_price,
+                        expiration_secs,
+                    },
+                },
+                FileStep::Wait { secs } => Step::Wait {
+                    duration: Duration::from_secs(secs),
+                },
+                FileStep::AssertBalance {
+                    account,
+                    amount,
+                    tolerance,
+                    coin_type,
+                } => Step::AssertBalance {
+                    account,
+                    amount: denomination(&coin_type)
+                        .parse(&amount)
+                        .with_context(invalid)?,
+                    tolerance: denomination(&coin_type)
+                        .parse(&tolerance)
+                        .with_context(invalid)?,
+                    coin_type,
+                },
+                FileStep::Snapshot { stage } => Step::Snapshot { stage },
+            });
+        }
+
+        let name = match self.name {
+            Some(name) => name,
+            None => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "scenario".to_string()),
+        };
+        Ok(Scenario {
+            name,
+            accounts: self.accounts,
+            steps,
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::PendingTransaction;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::account;
+use crate::pool::NodePool;
+use crate::receipt::{self, Receipt};
+use crate::retry::RetryPolicy;
+use crate::transaction::{self, Status};
+
+// Validation errors meaning the local sequence number no longer matches the chain's
+const RESYNC_STATUSES: [&str; 3] = [
+    "SEQUENCE_NUMBER_TOO_OLD",
+    "SEQUENCE_NUMBER_TOO_NEW",
+    "TRANSACTION_EXPIRED",
+];
+
+// Hands out one sender's sequence numbers locally, so many transfers can be in flight at once
+// instead of each waiting for the last to commit. The node runs them in sequence number order
+// whatever order they arrive in. When it says the local count has drifted, or a transfer
+// expires and leaves a gap the later ones cannot get past, the count is reset from the chain.
+// The commands do not pipeline: batch journals each row before signing the next, so a resumed
+// run knows exactly which rows may have been paid, and bench measures one transfer in flight
+// per sender, adding senders rather than depth for more load
+pub struct SequenceManager {
+    nodes: NodePool,
+    retry: RetryPolicy,
+    chain_id: u8,
+    address: AccountAddress,
+    // Its sequence number is the next one to hand out
+    account: Mutex<LocalAccount>,
+}
+
+// A transfer the node accepted but that may not have committed yet
+pub struct InFlight {
+    pub sequence_number: u64,
+    pub expiration_timestamp_secs: u64,
+    pub pending: PendingTransaction,
+}
+
+impl SequenceManager {
+    // Start counting from the sender's onchain sequence number. Submissions that fail
+    // transiently are retried under `retry` before any of this comes into play
+    pub async fn new(
+        nodes: &NodePool,
+        retry: RetryPolicy,
+        mut account: LocalAccount,
+    ) -> Result<Self> {
+        let chain_id = transaction::chain_id(nodes, &retry).await?;
+        *account.sequence_number_mut() =
+            onchain_sequence_number(nodes, &retry, account.address()).await?;
+
+        Ok(Self {
+            nodes: nodes.clone(),
+            retry,
+            chain_id,
+            address: account.address(),
+            account: Mutex::new(account),
+        })
+    }
+
+    pub fn address(&self) -> AccountAddress {
+        self.address
+    }
+
+    // The sequence number the next transfer will be signed with
+    pub fn next_sequence_number(&self) -> u64 {
+        self.account.lock().unwrap().sequence_number()
+    }
+
+    // Sign a transfer with the next sequence number and submit it without waiting for it to
+    // commit
+    pub async fn submit_transfer(
+        &self,
+        recipient: AccountAddress,
+        amount: u64,
+        options: &TransferOptions<'_>,
+    ) -> Result<InFlight> {
+        let signed = {
+            let mut account = self.account.lock().unwrap();
+            let builder = transaction::transfer_builder(
+                account.address(),
+                account.sequence_number(),
+                recipient,
+                amount,
+                options,
+                self.chain_id,
+            )?;
+            account.sign_with_transaction_builder(builder)
+        };
+        let sequence_number = signed.sequence_number();
+
+        match transaction::submit(&self.nodes, &self.retry, &signed).await {
+            Ok(pending) => Ok(InFlight {
+                sequence_number,
+                expiration_timestamp_secs: signed.expiration_timestamp_secs(),
+                pending,
+            }),
+            Err(err) => {
+                let err = err.context(format!(
+                    "Failed to submit transfer with sequence number {}",
+                    sequence_number
+                ));
+                match needs_resync(&err) {
+                    true => {
+                        self.resync().await.with_context(|| format!("{:#}", err))?;
+                    }
+                    false => self.release(sequence_number),
+                }
+                Err(err)
+            }
+        }
+    }
+
+    // Wait for a transfer to commit. One that is confirmed to have expired instead leaves a
+    // gap in the sequence numbers, so the count is resynced to fill it. One that is only slow
+    // may still commit, and resyncing then would hand its sequence number out twice
+    pub async fn wait(&self, in_flight: &InFlight, timeout: Duration) -> Result<Receipt> {
+        let err = match receipt::wait(&self.nodes, &self.retry, &in_flight.pending, timeout).await {
+            Ok(receipt) => return Ok(receipt),
+            Err(err) => err,
+        };
+
+        let status = transaction::status(
+            &self.nodes,
+            &self.retry,
+            self.address,
+            in_flight.sequence_number,
+            in_flight.expiration_timestamp_secs,
+        )
+        .await
+        .with_context(|| format!("{:#}", err))?;
+        if status == Status::Expired {
+            self.resync().await.with_context(|| format!("{:#}", err))?;
+        }
+
+        Err(err)
+    }
+
+    // Reset the count to the onchain sequence number, returning it
+    pub async fn resync(&self) -> Result<u64> {
+        let sequence_number =
+            onchain_sequence_number(&self.nodes, &self.retry, self.address).await?;
+        *self.account.lock().unwrap().sequence_number_mut() = sequence_number;
+
+        Ok(sequence_number)
+    }
+
+    // The account, counting from the next sequence number, e.g. to save it afterwards
+    pub fn into_account(self) -> LocalAccount {
+        self.account.into_inner().unwrap()
+    }
+
+    // A transfer the node refused for some other reason never used its sequence number.
+    // Take it back if nothing was handed out since, otherwise the gap stays until a resync
+    fn release(&self, sequence_number: u64) {
+        let mut account = self.account.lock().unwrap();
+        if account.sequence_number() == sequence_number + 1 {
+            *account.sequence_number_mut() = sequence_number;
+        }
+    }
+}
+
+// The status only appears in the node's error message, so look for it in the whole chain
+fn needs_resync(err: &anyhow::Error) -> bool {
+    let message = format!("{:?}", err);
+    RESYNC_STATUSES
+        .iter()
+        .any(|status| message.contains(status))
+}
+
+async fn onchain_sequence_number(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    address: AccountAddress,
+) -> Result<u64> {
+    let account = account::get(nodes, retry, address)
+        .await?
+        .with_context(|| format!("Account {} does not exist", address.to_hex_literal()))?;
+
+    Ok(account.sequence_number)
+}
@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::transaction::{RawTransaction, TransactionPayload};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::api_json;
+use crate::config::APTOS_COIN_TYPE;
+
+// What a transaction will do, decoded from the transaction itself so it can be reviewed
+// before it is signed or sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary {
+    // In plain words, such as `send 1000 octas of AptosCoin from 0xa11ce to 0xb0b`
+    pub description: String,
+    pub sender: String,
+    pub sequence_number: u64,
+    pub chain_id: u8,
+    pub expiration_timestamp_secs: u64,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    // The entry function called, with its type arguments
+    pub function: String,
+    // Decoded for the functions we know, hex encoded BCS for the rest
+    pub arguments: Vec<String>,
+}
+
+// A payment made through `0x1::coin::transfer` or `0x1::aptos_account::transfer`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinTransfer {
+    pub coin_type: String,
+    pub recipient: AccountAddress,
+    pub amount: u64,
+}
+
+// The payload of a transaction, read from BCS or from the API
+struct Call {
+    function: String,
+    type_arguments: Vec<String>,
+    arguments: Vec<String>,
+    transfer: Option<CoinTransfer>,
+}
+
+impl CoinTransfer {
+    pub fn decode(payload: &TransactionPayload) -> Option<Self> {
+        let function = match payload {
+            TransactionPayload::EntryFunction(function) => function,
+            _ => return None,
+        };
+        if *function.module().address() != AccountAddress::ONE {
+            return None;
+        }
+        let coin_type = match (
+            function.module().name().as_str(),
+            function.function().as_str(),
+            function.ty_args(),
+        ) {
+            ("coin", "transfer", [coin_type]) => coin_type.to_string(),
+            ("aptos_account", "transfer", []) => APTOS_COIN_TYPE.to_string(),
+            _ => return None,
+        };
+        match function.args() {
+            [recipient, amount] => Some(Self {
+                coin_type,
+                recipient: bcs::from_bytes(recipient).ok()?,
+                amount: bcs::from_bytes(amount).ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    // The same payment as the node's API renders it, with its arguments as JSON strings
+    pub fn from_json(
+        function: &str,
+        type_arguments: &[String],
+        arguments: &[String],
+    ) -> Option<Self> {
+        let coin_type = match (function, type_arguments) {
+            ("0x1::coin::transfer", [coin_type]) => coin_type.clone(),
+            ("0x1::aptos_account::transfer", []) => APTOS_COIN_TYPE.to_string(),
+            _ => return None,
+        };
+        match arguments {
+            [recipient, amount] => Some(Self {
+                coin_type,
+                recipient: AccountAddress::from_hex_literal(recipient).ok()?,
+                amount: amount.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    // `1000 octas of AptosCoin`. Other coins are counted in their smallest unit as well, since
+    // their decimals are not part of the transaction
+    pub fn describe_amount(&self) -> String {
+        let name = self
+            .coin_type
+            .rsplit("::")
+            .next()
+            .unwrap_or(&self.coin_type);
+        match self.coin_type == APTOS_COIN_TYPE {
+            true => format!("{} octas of {}", self.amount, name),
+            false => format!("{} base units of {}", self.amount, name),
+        }
+    }
+}
+
+impl Summary {
+    pub fn of(raw: &RawTransaction) -> Result<Self> {
+        // Walked as JSON for the fields RawTransaction has no accessors for
+        let json = serde_json::to_value(raw)?;
+        let field = |name: &str| {
+            json[name]
+                .as_u64()
+                .with_context(|| format!("Transaction has no {}", name))
+        };
+        let payload = raw.clone().into_payload();
+
+        let (function, type_arguments, arguments) = match &payload {
+            TransactionPayload::EntryFunction(function) => (
+                format!(
+                    "{}::{}::{}",
+                    function.module().address().to_hex_literal(),
+                    function.module().name(),
+                    function.function()
+                ),
+                function.ty_args().iter().map(ToString::to_string).collect(),
+                function
+                    .args()
+                    .iter()
+                    .map(|arg| format!("0x{}", hex::encode(arg)))
+                    .collect(),
+            ),
+            TransactionPayload::Script(_) => ("script".to_string(), Vec::new(), Vec::new()),
+            _ => ("module bundle".to_string(), Vec::new(), Vec::new()),
+        };
+
+        Ok(Self::new(
+            raw.sender(),
+            raw.sequence_number(),
+            u8::try_from(field("chain_id")?).context("Chain id is out of range")?,
+            raw.expiration_timestamp_secs(),
+            field("max_gas_amount")?,
+            field("gas_unit_price")?,
+            Call {
+                transfer: CoinTransfer::decode(&payload),
+                function,
+                type_arguments,
+                arguments,
+            },
+        ))
+    }
+
+    // A user transaction as the node's API renders it, pending or committed. It does not say
+    // which chain it is on, so that comes from the node
+    pub fn from_json(transaction: &Value, chain_id: u8) -> Result<Self> {
+        let field = |name: &str| api_json::u64_field(transaction, name, "Transaction");
+        let sender = transaction["sender"]
+            .as_str()
+            .and_then(|sender| AccountAddress::from_hex_literal(sender).ok())
+            .context("Transaction has no sender")?;
+        let payload = &transaction["payload"];
+        let strings = |name: &str| -> Vec<String> {
+            payload[name]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|value| match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+                .collect()
+        };
+
+        let function = match payload["function"].as_str() {
+            Some(function) => function.to_string(),
+            None => payload["type"]
+                .as_str()
+                .unwrap_or("unknown payload")
+                .to_string(),
+        };
+        let type_arguments = strings("type_arguments");
+        let arguments = strings("arguments");
+
+        Ok(Self::new(
+            sender,
+            field("sequence_number")?,
+            chain_id,
+            field("expiration_timestamp_secs")?,
+            field("max_gas_amount")?,
+            field("gas_unit_price")?,
+            Call {
+                transfer: CoinTransfer::from_json(&function, &type_arguments, &arguments),
+                function,
+                type_arguments,
+                arguments,
+            },
+        ))
+    }
+
+    fn new(
+        sender: AccountAddress,
+        sequence_number: u64,
+        chain_id: u8,
+        expiration_timestamp_secs: u64,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+        call: Call,
+    ) -> Self {
+        let mut function = call.function;
+        if !call.type_arguments.is_empty() {
+            function = format!("{}<{}>", function, call.type_arguments.join(", "));
+        }
+        let (description, arguments) = match call.transfer {
+            Some(transfer) => (
+                format!(
+                    "send {} from {} to {}",
+                    transfer.describe_amount(),
+                    sender.to_hex_literal(),
+                    transfer.recipient.to_hex_literal()
+                ),
+                vec![
+                    transfer.recipient.to_hex_literal(),
+                    transfer.amount.to_string(),
+                ],
+            ),
+            None => (format!("call {}", function), call.arguments),
+        };
+
+        Self {
+            description,
+            sender: sender.to_hex_literal(),
+            sequence_number,
+            chain_id,
+            expiration_timestamp_secs,
+            max_gas_amount,
+            gas_unit_price,
+            function,
+            arguments,
+        }
+    }
+
+    // The most the sender can be charged for gas
+    pub fn max_fee(&self) -> u64 {
+        self.max_gas_amount.saturating_mul(self.gas_unit_price)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock is before the unix epoch")
+            .as_secs();
+        let expiry = match self.expiration_timestamp_secs.checked_sub(now) {
+            Some(left) => format!("in {}s", left),
+            None => format!("expired {}s ago", now - self.expiration_timestamp_secs),
+        };
+
+        writeln!(f, "Summary: {}", self.description)?;
+        writeln!(f, "Sender: {}", self.sender)?;
+        writeln!(f, "Sequence number: {}", self.sequence_number)?;
+        writeln!(f, "Chain id: {}", self.chain_id)?;
+        writeln!(
+            f,
+            "Expires: {} ({})",
+            self.expiration_timestamp_secs, expiry
+        )?;
+        writeln!(
+            f,
+            "Max gas: {} at {} octas per unit, up to {} octas",
+            self.max_gas_amount,
+            self.gas_unit_price,
+            self.max_fee()
+        )?;
+        writeln!(f, "Function: {}", self.function)?;
+        write!(f, "Arguments: {}", self.arguments.join(", "))
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::crypto::ed25519::Ed25519Signature;
+use aptos_sdk::move_types::language_storage::TypeTag;
+use aptos_sdk::rest_client::{Client, PendingTransaction};
+use aptos_sdk::transaction_builder::{aptos_stdlib, TransactionBuilder};
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::chain_id::ChainId;
+use aptos_sdk::types::transaction::authenticator::TransactionAuthenticator;
+use aptos_sdk::types::transaction::SignedTransaction;
+use aptos_sdk::types::LocalAccount;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::api_json;
+use crate::coin;
+use crate::pool::NodePool;
+use crate::retry::RetryPolicy;
+
+// The same `0x1::coin::transfer` transaction `CoinClient::transfer` builds, so it can be
+// signed and inspected before (or instead of) being submitted
+pub fn transfer_builder(
+    sender: AccountAddress,
+    sequence_number: u64,
+    recipient: AccountAddress,
+    amount: u64,
+    options: &TransferOptions<'_>,
+    chain_id: u8,
+) -> Result<TransactionBuilder> {
+    let coin_type = TypeTag::from_str(options.coin_type)
+        .with_context(|| format!("Invalid coin type '{}'", options.coin_type))?;
+    let expiration_timestamp_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before the unix epoch")
+        .as_secs()
+        + options.timeout_secs;
+
+    Ok(TransactionBuilder::new(
+        aptos_stdlib::coin_transfer(coin_type, recipient, amount),
+        expiration_timestamp_secs,
+        ChainId::new(chain_id),
+    )
+    .sender(sender)
+    .sequence_number(sequence_number)
+    .max_gas_amount(options.max_gas_amount)
+    .gas_unit_price(options.gas_unit_price))
+}
+
+// Sign a transfer with the sender's next sequence number for the chain the nodes are on. The
+// sequence number is used up from here on, whether or not the transaction is ever submitted
+pub async fn sign_transfer(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    sender: &mut LocalAccount,
+    recipient: AccountAddress,
+    amount: u64,
+    options: &TransferOptions<'_>,
+) -> Result<SignedTransaction> {
+    let chain_id = chain_id(nodes, retry).await?;
+    let builder = transfer_builder(
+        sender.address(),
+        sender.sequence_number(),
+        recipient,
+        amount,
+        options,
+        chain_id,
+    )?;
+
+    Ok(sender.sign_with_transaction_builder(builder))
+}
+
+// Sign a transfer once and submit it, as `CoinClient::transfer` would but with retries. The
+// sender's sequence number is used up even if every attempt fails
+pub async fn transfer(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    sender: &mut LocalAccount,
+    recipient: AccountAddress,
+    amount: u64,
+    options: &TransferOptions<'_>,
+) -> Result<PendingTransaction> {
+    let signed = sign_transfer(nodes, retry, sender, recipient, amount, options).await?;
+
+    submit(nodes, retry, &signed).await
+}
+
+// Submit a signed transaction, retrying transient failures with the very same transaction so
+// a retry can never pay twice. An attempt that failed on its way back may still have reached
+// the node, in which case the next one is refused as a duplicate or as too old, so after a
+// failed retry the node is asked for the transaction by hash before giving up. Retries may
+// go to another node, which takes the same transaction just as well
+pub async fn submit(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    signed: &SignedTransaction,
+) -> Result<PendingTransaction> {
+    let mut resubmitting = false;
+    nodes
+        .run(retry, |client| {
+            let resubmitted = std::mem::replace(&mut resubmitting, true);
+            async move {
+                let err = match client.submit(signed).await {
+                    Ok(response) => return Ok(response.into_inner()),
+                    Err(err) => anyhow::Error::from(err).context("Failed to submit transaction"),
+                };
+                if resubmitted {
+                    if let Some(pending) = submitted(&client, signed).await {
+                        return Ok(pending);
+                    }
+                }
+                Err(err)
+            }
+        })
+        .await
+}
+
+// Where a transaction that was sent stands, as far as its sender's account tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    // The onchain sequence number has moved past it
+    Committed,
+    // The chain's clock passed its expiration before that happened, so it never can commit
+    Expired,
+    // Neither yet, so it may still commit
+    Pending,
+}
+
+// Judge the transaction `address` signed with `sequence_number` from the account alone. This
+// assumes nothing else sends from the account, or anything that took the sequence number
+// would count as this transaction committing
+pub async fn status(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    address: AccountAddress,
+    sequence_number: u64,
+    expiration_timestamp_secs: u64,
+) -> Result<Status> {
+    let response = nodes
+        .run(retry, |client| async move {
+            Ok(client.get_account(address).await?)
+        })
+        .await
+        .with_context(|| format!("Could not fetch account {}", address.to_hex_literal()))?;
+
+    if response.inner().sequence_number > sequence_number {
+        return Ok(Status::Committed);
+    }
+    // Block timestamps only go up, and none at or past the expiration can include it
+    if response.state().timestamp_usecs >= expiration_timestamp_secs.saturating_mul(1_000_000) {
+        return Ok(Status::Expired);
+    }
+
+    Ok(Status::Pending)
+}
+
+// The node's view of a transaction it already has, pending or committed. Committed ones carry
+// every field of a pending one, so either reads as one
+async fn submitted(rest_client: &Client, signed: &SignedTransaction) -> Option<PendingTransaction> {
+    let hash = signed.clone().committed_hash();
+    let transaction = rest_client
+        .get_transaction_by_hash(hash)
+        .await
+        .ok()?
+        .into_inner();
+    serde_json::from_value(api_json::transaction(&transaction).ok()?).ok()
+}
+
+pub async fn chain_id(nodes: &NodePool, retry: &RetryPolicy) -> Result<u8> {
+    Ok(nodes
+        .run(retry, |client| async move {
+            Ok(client.get_ledger_information().await?)
+        })
+        .await
+        .context("Failed to get chain ID")?
+        .into_inner()
+        .chain_id)
+}
+
+// The outcome the node predicts for a transaction, and the CoinStore balances it would write
+pub struct Simulation {
+    pub success: bool,
+    pub vm_status: String,
+    pub gas_used: u64,
+    pub gas_unit_price: u64,
+    pub coin_writes: Vec<(AccountAddress, u64)>,
+}
+
+impl Simulation {
+    pub fn fee(&self) -> u64 {
+        self.gas_used * self.gas_unit_price
+    }
+}
+
+// Run a signed transaction through the node's simulate endpoint. Nodes refuse to simulate
+// transactions carrying a valid signature, since the request could then simply be submitted,
+// so the signature is swapped for zeroes first
+pub async fn simulate(
+    nodes: &NodePool,
+    retry: &RetryPolicy,
+    signed: &SignedTransaction,
+    coin_type: &str,
+) -> Result<Simulation> {
+    let unsigned = match signed.authenticator() {
+        TransactionAuthenticator::Ed25519 { public_key, .. } => SignedTransaction::new(
+            signed.clone().into_raw_transaction(),
+            public_key,
+            Ed25519Signature::try_from(&[0u8; 64][..])?,
+        ),
+        _ => signed.clone(),
+    };
+
+    let unsigned = &unsigned;
+    let transactions = nodes
+        .run(retry, |client| async move {
+            Ok(client.simulate(unsigned).await?)
+        })
+        .await
+        .context("Failed to simulate transaction")?
+        .into_inner();
+    let transaction = transactions
+        .first()
+        .context("Node returned no simulation result")?;
+
+    let json = api_json::transaction(transaction)?;
+    let store_type = coin::coin_store_type(coin_type);
+    let coin_writes = json["changes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|change| {
+            change["type"] == "write_resource" && change["data"]["type"] == store_type.as_str()
+        })
+        .filter_map(|change| {
+            let address = AccountAddress::from_hex_literal(change["address"].as_str()?).ok()?;
+            Some((address, coin::coin_store_value(&change["data"]["data"])?))
+        })
+        .collect();
+
+    let (success, vm_status) = api_json::execution_status(&json);
+
+    Ok(Simulation {
+        success,
+        vm_status,
+        gas_used: api_json::u64_field(&json, "gas_used", "Simulation result")?,
+        gas_unit_price: api_json::u64_field(&json, "gas_unit_price", "Simulation result")?,
+        coin_writes,
+    })
+}
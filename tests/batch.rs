@@ -0,0 +1,252 @@
+// Resuming batches from their journal against the mock node. Whatever state a run was cut
+// off in, a rerun must pay every row exactly once
+mod common;
+
+use aptos_client_test::transaction;
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::transaction::SignedTransaction;
+use aptos_sdk::types::LocalAccount;
+use common::{stdout, MockNode, APTOS_COIN, CHAIN_ID};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+const RECIPIENTS: [&str; 3] = ["0xb0b", "0xca201", "0xda7e"];
+
+fn sender() -> LocalAccount {
+    LocalAccount::from_private_key(SENDER_KEY, 0).unwrap()
+}
+
+fn address() -> String {
+    sender().address().to_hex_literal()
+}
+
+fn start() -> MockNode {
+    let node = MockNode::start();
+    node.set_balance(&address(), 100_000);
+    for recipient in RECIPIENTS {
+        node.set_balance(recipient, 0);
+    }
+    node
+}
+
+// A batch paying 1000 octas to each of the first `rows` recipients
+fn batch_file(home: &Path, rows: usize) -> PathBuf {
+    let path = home.join("payouts.csv");
+    let lines: Vec<String> = RECIPIENTS[..rows]
+        .iter()
+        .map(|recipient| format!("{},1000 octas\n", recipient))
+        .collect();
+    fs::write(&path, lines.concat()).unwrap();
+    path
+}
+
+fn journal(home: &Path) -> PathBuf {
+    home.join("payouts.csv.journal")
+}
+
+fn run_batch(node: &MockNode, home: &Path, file: &Path) -> Output {
+    node.run(
+        home,
+        &[
+            "--max-gas-amount",
+            "1000",
+            "--gas-unit-price",
+            "1",
+            "batch",
+            "--from",
+            SENDER_KEY,
+            file.to_str().unwrap(),
+        ],
+    )
+}
+
+// A journal left by a run that signed row 1 and was killed before it learned the outcome
+fn write_pending(home: &Path, sequence_number: u64, expiration_timestamp_secs: u64) {
+    let entry = json!({
+        "status": "pending",
+        "row": 1,
+        "recipient": RECIPIENTS[0],
+        "amount": 1000,
+        "sender": address(),
+        "sequence_number": sequence_number,
+        "expiration_timestamp_secs": expiration_timestamp_secs,
+    });
+    fs::write(journal(home), format!("{}\n", entry)).unwrap();
+}
+
+// The transfer row 1 would have been signed with
+fn signed_row_one() -> SignedTransaction {
+    let mut sender = sender();
+    let options = TransferOptions {
+        max_gas_amount: 1_000,
+        gas_unit_price: 1,
+        timeout_secs: 60,
+        coin_type: APTOS_COIN,
+    };
+    let builder = transaction::transfer_builder(
+        sender.address(),
+        0,
+        AccountAddress::from_hex_literal(RECIPIENTS[0]).unwrap(),
+        1_000,
+        &options,
+        CHAIN_ID,
+    )
+    .unwrap();
+    sender.sign_with_transaction_builder(builder)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[test]
+fn rerun_after_full_success_sends_nothing() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    let file = batch_file(home.path(), 2);
+    stdout(&run_batch(&node, home.path(), &file));
+    assert_eq!(node.transactions_from(&address()).len(), 2);
+
+    let output = stdout(&run_batch(&node, home.path(), &file));
+
+    assert_eq!(output.matches("already paid").count(), 2, "{}", output);
+    assert_eq!(node.transactions_from(&address()).len(), 2);
+    assert_eq!(node.balance(RECIPIENTS[0]), 1_000);
+    assert_eq!(node.balance(RECIPIENTS[1]), 1_000);
+}
+
+#[test]
+fn killed_run_resumes_mid_batch() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    stdout(&run_batch(&node, home.path(), &batch_file(home.path(), 2)));
+
+    // Cut off after row 2 was submitted but before its commit was journaled, and before row 3
+    // was reached at all
+    let contents = fs::read_to_string(journal(home.path())).unwrap();
+    let mut lines: Vec<&str> = contents.lines().collect();
+    assert!(lines.pop().unwrap().contains("\"committed\""));
+    fs::write(journal(home.path()), lines.join("\n") + "\n").unwrap();
+
+    let output = stdout(&run_batch(&node, home.path(), &batch_file(home.path(), 3)));
+
+    assert_eq!(output.matches("already paid").count(), 2, "{}", output);
+    assert_eq!(node.transactions_from(&address()).len(), 3);
+    for recipient in RECIPIENTS {
+        assert_eq!(node.balance(recipient), 1_000, "{}", recipient);
+    }
+}
+
+#[test]
+fn submitted_row_still_pending_is_waited_for_not_resent() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    let signed = signed_row_one();
+    write_pending(home.path(), 0, signed.expiration_timestamp_secs());
+    node.submit_later(signed, Duration::from_secs(2));
+
+    let output = stdout(&run_batch(&node, home.path(), &batch_file(home.path(), 1)));
+
+    assert!(output.contains("already paid"), "{}", output);
+    assert_eq!(node.transactions_from(&address()).len(), 1);
+    assert_eq!(node.balance(RECIPIENTS[0]), 1_000);
+}
+
+#[test]
+fn submitted_row_that_expired_is_sent_again() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    write_pending(home.path(), 0, now_secs() - 10);
+
+    let output = stdout(&run_batch(&node, home.path(), &batch_file(home.path(), 1)));
+
+    assert!(output.contains("paid in"), "{}", output);
+    assert!(!output.contains("already paid"), "{}", output);
+    assert_eq!(node.transactions_from(&address()).len(), 1);
+    assert_eq!(node.balance(RECIPIENTS[0]), 1_000);
+}
+
+#[test]
+fn row_to_a_missing_recipient_fails_without_being_sent() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    let file = home.path().join("payouts.csv");
+    fs::write(&file, "0xb0b,1000 octas\n0xdead,1000 octas\n").unwrap();
+
+    let output = run_batch(&node, home.path(), &file);
+
+    assert!(!output.status.success());
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(report.contains("to 0xb0b: paid in"), "{}", report);
+    assert!(
+        report.contains("to 0xdead: FAILED: recipient 0xdead has no onchain account"),
+        "{}",
+        report
+    );
+    assert_eq!(node.transactions_from(&address()).len(), 1);
+}
+
+#[test]
+fn auto_create_makes_missing_recipients_before_paying_them() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    let file = home.path().join("payouts.csv");
+    fs::write(&file, "0xdead,1000 octas\n").unwrap();
+    let mut args = vec!["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+    args.extend(["batch", "--auto-create", "--from", SENDER_KEY]);
+    args.push(file.to_str().unwrap());
+
+    let output = stdout(&node.run(home.path(), &args));
+
+    assert!(output.contains("account created, paid in"), "{}", output);
+    assert_eq!(node.balance("0xdead"), 1_000);
+}
+
+#[test]
+fn rerun_after_rows_are_reordered_is_refused() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    let file = batch_file(home.path(), 2);
+    stdout(&run_batch(&node, home.path(), &file));
+    let reordered = format!(
+        "{},1000 octas\n{},1000 octas\n",
+        RECIPIENTS[1], RECIPIENTS[0]
+    );
+    fs::write(&file, reordered).unwrap();
+
+    let output = run_batch(&node, home.path(), &file);
+
+    assert!(!output.status.success());
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("does not match journal"), "{}", error);
+    assert_eq!(node.transactions_from(&address()).len(), 2);
+}
+
+#[test]
+fn rerun_after_a_row_is_inserted_pays_nobody() {
+    let node = start();
+    let home = tempfile::tempdir().unwrap();
+    let file = batch_file(home.path(), 2);
+    stdout(&run_batch(&node, home.path(), &file));
+    let inserted = format!(
+        "{},1000 octas\n{},1000 octas\n{},1000 octas\n",
+        RECIPIENTS[2], RECIPIENTS[0], RECIPIENTS[1]
+    );
+    fs::write(&file, inserted).unwrap();
+
+    let output = run_batch(&node, home.path(), &file);
+
+    assert!(!output.status.success());
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("Row 1 of"), "{}", error);
+    assert_eq!(node.transactions_from(&address()).len(), 2);
+    assert_eq!(node.balance(RECIPIENTS[2]), 0);
+}
@@ -0,0 +1,108 @@
+// Load generation against the mock node and faucet, which commits every transfer at once
+mod common;
+
+use common::MockNode;
+use serde_json::Value;
+
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+
+fn bench(node: &MockNode, load: &[&str]) -> Value {
+    let home = tempfile::tempdir().unwrap();
+    let mut args = GAS.to_vec();
+    args.extend(["--output", "json", "bench", "--duration-secs", "2"]);
+    args.extend(load);
+
+    let output = common::stdout(&node.run(home.path(), &args));
+    serde_json::from_str(&output).unwrap()
+}
+
+#[test]
+fn closed_loop_keeps_every_sender_busy() {
+    let node = MockNode::start();
+
+    let summary = bench(&node, &["--concurrency", "3"]);
+
+    assert_eq!(summary["mode"], "closed");
+    assert_eq!(summary["senders"], 3);
+    assert_eq!(summary["failed"], 0, "{}", summary);
+    assert_eq!(summary["aborted"], 0, "{}", summary);
+    let committed = summary["committed"].as_u64().unwrap();
+    assert!(committed >= 3, "{}", summary);
+    assert_eq!(summary["submitted"].as_u64().unwrap(), committed);
+    assert!(summary["committed_tps"].as_f64().unwrap() > 0.0);
+    let latency = &summary["latency_ms"];
+    assert!(latency["p50"].as_f64().unwrap() <= latency["p100"].as_f64().unwrap());
+}
+
+#[test]
+fn open_loop_fires_at_the_target_rate() {
+    let node = MockNode::start();
+
+    let summary = bench(&node, &["--rate", "10", "--senders", "4"]);
+
+    assert_eq!(summary["mode"], "open");
+    assert_eq!(summary["senders"], 4);
+    assert_eq!(summary["failed"], 0, "{}", summary);
+    // Two seconds at 10 a second, less whatever found every sender busy
+    let fired = summary["submitted"].as_u64().unwrap() + summary["skipped"].as_u64().unwrap();
+    assert!((18..=21).contains(&fired), "{}", summary);
+    assert_eq!(summary["committed"], summary["submitted"]);
+}
+
+#[test]
+fn histogram_log_is_written() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let log = home.path().join("latency.hlog");
+    let mut args = GAS.to_vec();
+    args.extend(["bench", "--concurrency", "1", "--duration-secs", "1"]);
+    args.extend(["--histogram-log", log.to_str().unwrap()]);
+
+    let output = common::stdout(&node.run(home.path(), &args));
+
+    assert!(output.contains("Committed: "), "{}", output);
+    assert!(output.contains("p50"), "{}", output);
+    let log = std::fs::read_to_string(log).unwrap();
+    assert!(log.contains("#[StartTime: "), "{}", log);
+    assert!(
+        log.lines().any(|line| line.starts_with("0.000,")),
+        "{}",
+        log
+    );
+}
+
+#[test]
+fn open_loop_counts_arrivals_a_busy_sender_could_not_keep_up_with() {
+    let node = MockNode::start();
+
+    // One sender cannot send two thousand transfers a second, so arrivals queue behind it
+    let summary = bench(&node, &["--rate", "2000", "--senders", "1"]);
+
+    let submitted = summary["submitted"].as_u64().unwrap();
+    let failed = summary["failed"].as_u64().unwrap();
+    let skipped = summary["skipped"].as_u64().unwrap();
+    assert_eq!(submitted + failed + skipped, 4_000, "{}", summary);
+    assert!(skipped > 0, "{}", summary);
+    // Queued arrivals count their wait, so the slowest took far longer than one transfer
+    assert!(
+        summary["latency_ms"]["p100"].as_f64().unwrap() >= 100.0,
+        "{}",
+        summary
+    );
+}
+
+#[test]
+fn rates_without_a_usable_interval_are_refused() {
+    let node = MockNode::start();
+
+    for rate in ["0", "1e-300", "1e300", "NaN", "inf"] {
+        let home = tempfile::tempdir().unwrap();
+        let mut args = GAS.to_vec();
+        args.extend(["bench", "--duration-secs", "1", "--rate", rate]);
+
+        let output = node.run(home.path(), &args);
+
+        assert_eq!(output.status.code(), Some(1), "{}", rate);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("A bench rate must be between"));
+    }
+}
@@ -0,0 +1,408 @@
+// Recorded HTTP sessions. A `Recorder` sits between the binary and a real node and faucet and
+// keeps every exchange; a `Player` serves a saved cassette back so the same session runs
+// offline. Requests match on method, path and body, after redacting the parts that differ on
+// every run
+use aptos_sdk::crypto::ed25519::Ed25519Signature;
+use aptos_sdk::types::transaction::authenticator::TransactionAuthenticator;
+use aptos_sdk::types::transaction::{RawTransaction, SignedTransaction};
+use axum::body::Bytes;
+use axum::http::header::{HeaderName, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::{Extension, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::serve;
+
+const REDACTED: &str = "<redacted>";
+
+// Headers that describe one particular transfer of a body rather than the response itself
+pub const HOP_HEADERS: [&str; 4] = [
+    "connection",
+    "content-encoding",
+    "content-length",
+    "transfer-encoding",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    // Path and query string
+    pub path: String,
+    // Redacted body: JSON text, `bcs:` and hex for signed transactions, empty for none
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+// Exchanges in the order they happened
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Self {
+        let contents = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("could not read cassette {}: {}", path.display(), err));
+        serde_json::from_str(&contents)
+            .unwrap_or_else(|err| panic!("invalid cassette {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, path: &Path) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, serde_json::to_string_pretty(self).unwrap() + "\n").unwrap();
+    }
+}
+
+// Which parts of a request body are left out when it is stored and matched. Signatures change
+// with every signing and timestamps with every run, so both are redacted by default
+#[derive(Debug, Clone, Copy)]
+pub struct Redact {
+    pub signatures: bool,
+    pub timestamps: bool,
+}
+
+impl Default for Redact {
+    fn default() -> Self {
+        Self {
+            signatures: true,
+            timestamps: true,
+        }
+    }
+}
+
+impl Redact {
+    // The form a request body is stored and matched in. JSON fields named `signature` or
+    // holding a timestamp are blanked; BCS signed transactions get a zero signature and
+    // expiration
+    pub fn body(&self, body: &[u8]) -> String {
+        if body.is_empty() {
+            return String::new();
+        }
+        if let Ok(json) = serde_json::from_slice::<Value>(body) {
+            return self.json(json).to_string();
+        }
+        if let Ok(signed) = bcs::from_bytes::<SignedTransaction>(body) {
+            let redacted = bcs::to_bytes(&self.transaction(signed)).unwrap();
+            return format!("bcs:{}", hex::encode(redacted));
+        }
+        match std::str::from_utf8(body) {
+            Ok(text) => text.to_string(),
+            Err(_) => format!("hex:{}", hex::encode(body)),
+        }
+    }
+
+    fn json(&self, value: Value) -> Value {
+        match value {
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let redact = (self.signatures && key == "signature")
+                            || (self.timestamps && key.contains("timestamp"));
+                        let value = match redact {
+                            true => json!(REDACTED),
+                            false => self.json(value),
+                        };
+                        (key, value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(|value| self.json(value)).collect())
+            }
+            value => value,
+        }
+    }
+
+    fn transaction(&self, signed: SignedTransaction) -> SignedTransaction {
+        let (public_key, signature) = match signed.authenticator() {
+            TransactionAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            } => (public_key, signature),
+            _ => return signed,
+        };
+        let signature = match self.signatures {
+            true => Ed25519Signature::try_from(&[0u8; 64][..]).unwrap(),
+            false => signature,
+        };
+        let expiration_timestamp_secs = match self.timestamps {
+            true => 0,
+            false => signed.expiration_timestamp_secs(),
+        };
+        let raw = RawTransaction::new(
+            signed.sender(),
+            signed.sequence_number(),
+            signed.payload().clone(),
+            signed.max_gas_amount(),
+            signed.gas_unit_price(),
+            expiration_timestamp_secs,
+            signed.chain_id(),
+        );
+
+        SignedTransaction::new(raw, public_key, signature)
+    }
+}
+
+struct Recording {
+    node_url: String,
+    faucet_url: String,
+    redact: Redact,
+    client: reqwest::Client,
+    cassette: Cassette,
+}
+
+// A proxy that forwards faucet requests (`/mint`) to the faucet, everything else to the node,
+// and records each exchange
+pub struct Recorder {
+    url: String,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl Recorder {
+    pub fn start(node_url: &str, faucet_url: &str, redact: Redact) -> Self {
+        let recording = Arc::new(Mutex::new(Recording {
+            node_url: node_url.trim_end_matches('/').to_string(),
+            faucet_url: faucet_url.trim_end_matches('/').to_string(),
+            redact,
+            client: reqwest::Client::new(),
+            cassette: Cassette::default(),
+        }));
+        let url = serve(
+            Router::new()
+                .fallback(any(record))
+                .layer(Extension(recording.clone())),
+        );
+
+        Self { url, recording }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn cassette(&self) -> Cassette {
+        self.recording.lock().unwrap().cassette.clone()
+    }
+
+    pub fn save(&self, path: &Path) {
+        self.cassette().save(path);
+    }
+}
+
+async fn record(
+    Extension(recording): Extension<Arc<Mutex<Recording>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = path_and_query(&uri);
+    let (client, upstream, redact) = {
+        let recording = recording.lock().unwrap();
+        let upstream = match uri.path().starts_with("/mint") {
+            true => recording.faucet_url.clone(),
+            false => recording.node_url.clone(),
+        };
+        (recording.client.clone(), upstream, recording.redact)
+    };
+
+    let mut request = client
+        .request(method.clone(), format!("{}{}", upstream, path))
+        .body(body.to_vec());
+    if let Some(content_type) = headers.get(CONTENT_TYPE) {
+        request = request.header(CONTENT_TYPE, content_type.clone());
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(err) => return (StatusCode::BAD_GATEWAY, err.to_string()).into_response(),
+    };
+
+    let status = response.status().as_u16();
+    let response_headers = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| !HOP_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let response_body = match response.text().await {
+        Ok(body) => body,
+        Err(err) => return (StatusCode::BAD_GATEWAY, err.to_string()).into_response(),
+    };
+
+    let interaction = Interaction {
+        request: RecordedRequest {
+            method: method.to_string(),
+            path,
+            body: redact.body(&body),
+        },
+        response: RecordedResponse {
+            status,
+            headers: response_headers,
+            body: response_body,
+        },
+    };
+    let response = replay_response(&interaction.response);
+    recording
+        .lock()
+        .unwrap()
+        .cassette
+        .interactions
+        .push(interaction);
+
+    response
+}
+
+struct Playback {
+    redact: Redact,
+    interactions: Vec<Interaction>,
+    used: Vec<bool>,
+    missing: Vec<RecordedRequest>,
+}
+
+// Serves a cassette back. Each recorded exchange answers one request, in recorded order, so
+// polling sees the same sequence of responses it did when recording
+pub struct Player {
+    url: String,
+    playback: Arc<Mutex<Playback>>,
+}
+
+impl Player {
+    pub fn start(cassette: Cassette, redact: Redact) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        let playback = Arc::new(Mutex::new(Playback {
+            redact,
+            interactions: cassette.interactions,
+            used,
+            missing: Vec::new(),
+        }));
+        let url = serve(
+            Router::new()
+                .fallback(any(replay))
+                .layer(Extension(playback.clone())),
+        );
+
+        Self { url, playback }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // Requests the cassette had no answer for, in the order they arrived
+    pub fn missing(&self) -> Vec<RecordedRequest> {
+        self.playback.lock().unwrap().missing.clone()
+    }
+
+    pub fn assert_complete(&self) {
+        let missing = self.missing();
+        if missing.is_empty() {
+            return;
+        }
+
+        let requests: Vec<String> = missing
+            .iter()
+            .map(|request| format!("  {} {} {}", request.method, request.path, request.body))
+            .collect();
+        panic!(
+            "{} request(s) not in the cassette, record it again:\n{}",
+            missing.len(),
+            requests.join("\n")
+        );
+    }
+}
+
+async fn replay(
+    Extension(playback): Extension<Arc<Mutex<Playback>>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let mut playback = playback.lock().unwrap();
+    let request = RecordedRequest {
+        method: method.to_string(),
+        path: path_and_query(&uri),
+        body: playback.redact.body(&body),
+    };
+
+    let matching: Vec<usize> = (0..playback.interactions.len())
+        .filter(|&index| playback.interactions[index].request == request)
+        .collect();
+    let unused = matching
+        .iter()
+        .copied()
+        .find(|&index| !playback.used[index]);
+    // Reads may be repeated more often than when recording, say by a faster poll, so once a
+    // GET's recorded answers are used up the last one keeps being served. Writes never are
+    let index = match (unused, method) {
+        (Some(index), _) => Some(index),
+        (None, Method::GET) => matching.last().copied(),
+        (None, _) => None,
+    };
+
+    match index {
+        Some(index) => {
+            playback.used[index] = true;
+            replay_response(&playback.interactions[index].response)
+        }
+        // Answered with a status the client never retries, so a miss fails at once and is
+        // not hidden by a retry that happens to find a later recording
+        None => {
+            let message = format!(
+                "Request not in cassette: {} {} {}",
+                request.method, request.path, request.body
+            );
+            playback.missing.push(request);
+            let body = json!({
+                "code": 501,
+                "message": message,
+                "error_code": "not_implemented",
+                "vm_error_code": null,
+            });
+            (StatusCode::NOT_IMPLEMENTED, body.to_string()).into_response()
+        }
+    }
+}
+
+fn replay_response(recorded: &RecordedResponse) -> Response {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &recorded.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    let status = StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::BAD_GATEWAY);
+
+    (status, headers, recorded.body.clone()).into_response()
+}
+
+fn path_and_query(uri: &Uri) -> String {
+    uri.path_and_query()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| uri.path().to_string())
+}
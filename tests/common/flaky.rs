@@ -0,0 +1,169 @@
+// A proxy in front of a node and faucet that fails chosen requests with an HTTP error, either
+// instead of passing them on or after the server behind it has already acted on them
+use axum::body::Bytes;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::{Extension, Router};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+use super::cassette::HOP_HEADERS;
+use super::serve;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    // The request never reaches the server
+    Before,
+    // The server handles the request but its answer is lost
+    After,
+}
+
+// Fail the next `times` requests whose method matches and whose path, without the /v1 prefix,
+// starts with `path`
+#[derive(Debug, Clone)]
+pub struct Fault {
+    pub method: Method,
+    pub path: &'static str,
+    pub status: u16,
+    pub times: usize,
+    pub when: When,
+}
+
+struct State {
+    upstream: String,
+    client: reqwest::Client,
+    faults: Vec<Fault>,
+    // Method, path and body of every request, failed or not
+    requests: Vec<(Method, String, Vec<u8>)>,
+}
+
+pub struct Flaky {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl Flaky {
+    pub fn start(upstream: &str, faults: Vec<Fault>) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            upstream: upstream.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            faults,
+            requests: Vec::new(),
+        }));
+        let url = serve(
+            Router::new()
+                .fallback(any(forward))
+                .layer(Extension(state.clone())),
+        );
+
+        Self { url, state }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // Bodies of the requests made with `method` to paths starting with `path`
+    pub fn bodies(&self, method: &Method, path: &str) -> Vec<Vec<u8>> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|(request_method, request_path, _)| {
+                request_method == method && request_path.starts_with(path)
+            })
+            .map(|(_, _, body)| body.clone())
+            .collect()
+    }
+
+    // Failures still left to inject
+    pub fn remaining(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .faults
+            .iter()
+            .map(|fault| fault.times)
+            .sum()
+    }
+}
+
+async fn forward(
+    Extension(state): Extension<Arc<Mutex<State>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri
+        .path_and_query()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| uri.path().to_string());
+    // Faults and recorded requests name paths the way the mock node routes them
+    let route = path.strip_prefix("/v1").unwrap_or(&path).to_string();
+    let (client, upstream, fault) = {
+        let mut state = state.lock().unwrap();
+        state
+            .requests
+            .push((method.clone(), route.clone(), body.to_vec()));
+        let fault = state
+            .faults
+            .iter_mut()
+            .find(|fault| {
+                fault.times > 0 && fault.method == method && route.starts_with(fault.path)
+            })
+            .map(|fault| {
+                fault.times -= 1;
+                (fault.status, fault.when)
+            });
+        (state.client.clone(), state.upstream.clone(), fault)
+    };
+
+    if let Some((status, When::Before)) = fault {
+        return injected(status);
+    }
+
+    let mut request = client
+        .request(method, format!("{}{}", upstream, path))
+        .body(body.to_vec());
+    if let Some(content_type) = headers.get(CONTENT_TYPE) {
+        request = request.header(CONTENT_TYPE, content_type.clone());
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(err) => return (StatusCode::BAD_GATEWAY, err.to_string()).into_response(),
+    };
+
+    if let Some((status, When::After)) = fault {
+        return injected(status);
+    }
+
+    let status = response.status();
+    let mut response_headers = HeaderMap::new();
+    for (name, value) in response.headers() {
+        if !HOP_HEADERS.contains(&name.as_str()) {
+            response_headers.insert(name.clone(), value.clone());
+        }
+    }
+    let response_body = response.bytes().await.unwrap_or_default();
+
+    (status, response_headers, response_body).into_response()
+}
+
+// An error as a load balancer in front of a node might send it
+fn injected(status: u16) -> Response {
+    let status = StatusCode::from_u16(status).unwrap();
+    let body = json!({
+        "code": status.as_u16(),
+        "message": format!("Injected failure: {}", status),
+        "error_code": "internal_error",
+        "vm_error_code": null,
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    (status, headers, body.to_string()).into_response()
+}
@@ -0,0 +1,941 @@
+// A stand-in for an Aptos node and its faucet, served in process so the binary can be driven
+// end to end without a network. It keeps accounts, balances and sequence numbers in memory and
+// executes the two entry functions the client sends: `0x1::coin::transfer` from users and
+// `0x1::aptos_coin::mint` from the faucet
+#![allow(dead_code)]
+
+pub mod cassette;
+pub mod flaky;
+
+use aptos_sdk::move_types::vm_status::StatusCode as VmStatus;
+use aptos_sdk::transaction_builder::{aptos_stdlib, TransactionBuilder};
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::chain_id::ChainId;
+use aptos_sdk::types::transaction::authenticator::TransactionAuthenticator;
+use aptos_sdk::types::transaction::{SignedTransaction, TransactionPayload};
+use aptos_sdk::types::LocalAccount;
+use axum::body::Bytes;
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::{Extension, Json, Router};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const CHAIN_ID: u8 = 4;
+pub const APTOS_COIN: &str = "0x1::aptos_coin::AptosCoin";
+pub const PASSWORD: &str = "mock node password";
+
+// Every user transaction costs the same, so tests can work out fees exactly
+pub const GAS_USED: u64 = 10;
+
+// How far ahead of an account's sequence number a transaction may be and still wait in the
+// mempool for the gap to fill, as on a real node
+const MAX_SEQUENCE_NUMBER_GAP: u64 = 100;
+
+const ZERO_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Default)]
+struct Account {
+    sequence_number: u64,
+    // CoinStores by coin type
+    coins: HashMap<String, u64>,
+    deposit_events: u64,
+    withdraw_events: u64,
+}
+
+struct Ledger {
+    accounts: HashMap<AccountAddress, Account>,
+    // Committed transactions in version order, rendered as the API returns them
+    transactions: Vec<Value>,
+    // Accepted transactions whose sequence number is ahead of their sender's
+    parked: BTreeMap<(AccountAddress, u64), SignedTransaction>,
+    faucet: LocalAccount,
+    chain_id: u8,
+}
+
+impl Ledger {
+    fn new(chain_id: u8) -> Self {
+        let faucet = LocalAccount::generate(&mut rand::rngs::OsRng);
+        let mut accounts = HashMap::new();
+        accounts.insert(faucet.address(), Account::default());
+
+        Self {
+            accounts,
+            transactions: Vec::new(),
+            parked: BTreeMap::new(),
+            faucet,
+            chain_id,
+        }
+    }
+
+    fn version(&self) -> u64 {
+        self.transactions.len() as u64
+    }
+}
+
+pub struct MockNode {
+    url: String,
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl MockNode {
+    pub fn start() -> Self {
+        Self::start_on_chain(CHAIN_ID)
+    }
+
+    // A node of some other chain, such as mainnet's 1, for checks of which chain a network
+    // is on. `run` always expects CHAIN_ID, so drive it with the free `run` instead
+    pub fn start_on_chain(chain_id: u8) -> Self {
+        let ledger = Arc::new(Mutex::new(Ledger::new(chain_id)));
+        let url = serve(
+            Router::new()
+                .fallback(any(handle))
+                .layer(Extension(ledger.clone())),
+        );
+
+        Self { url, ledger }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // Give an account an APT balance, creating it if it does not exist yet
+    pub fn set_balance(&self, address: &str, amount: u64) {
+        self.set_coin_balance(address, APTOS_COIN, amount);
+    }
+
+    // Register a CoinStore for any coin type and set its balance
+    pub fn set_coin_balance(&self, address: &str, coin_type: &str, amount: u64) {
+        self.ledger
+            .lock()
+            .unwrap()
+            .accounts
+            .entry(parse_address(address).expect("invalid address"))
+            .or_default()
+            .coins
+            .insert(coin_type.to_string(), amount);
+    }
+
+    // APT balance, or zero for accounts without one
+    pub fn balance(&self, address: &str) -> u64 {
+        let address = parse_address(address).expect("invalid address");
+        self.ledger
+            .lock()
+            .unwrap()
+            .accounts
+            .get(&address)
+            .and_then(|account| account.coins.get(APTOS_COIN).copied())
+            .unwrap_or(0)
+    }
+
+    pub fn sequence_number(&self, address: &str) -> Option<u64> {
+        let address = parse_address(address).expect("invalid address");
+        self.ledger
+            .lock()
+            .unwrap()
+            .accounts
+            .get(&address)
+            .map(|account| account.sequence_number)
+    }
+
+    // Committed user transactions sent by `address`, as the API renders them
+    pub fn transactions_from(&self, address: &str) -> Vec<Value> {
+        let address = parse_address(address).expect("invalid address");
+        self.ledger
+            .lock()
+            .unwrap()
+            .transactions
+            .iter()
+            .filter(|transaction| transaction["sender"] == address.to_hex_literal().as_str())
+            .cloned()
+            .collect()
+    }
+
+    pub fn run(&self, home: &Path, args: &[&str]) -> Output {
+        run(&self.url, Some(CHAIN_ID), home, args)
+    }
+
+    // Take a transaction into the mempool after `delay`, as if its submission had been cut
+    // off and it only made its way through later. Until then the chain has not seen it
+    pub fn submit_later(&self, signed: SignedTransaction, delay: Duration) {
+        let ledger = self.ledger.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            let mut ledger = ledger.lock().unwrap();
+            let sender = signed.sender();
+            ledger
+                .parked
+                .insert((sender, signed.sequence_number()), signed);
+            commit_ready(&mut ledger, sender);
+        });
+    }
+}
+
+// Serve `router` on a free local port from its own thread and runtime, for as long as the test
+// process lives, and return its URL
+pub fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind test server");
+    listener.set_nonblocking(true).unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    std::thread::spawn(move || {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async move {
+                axum::Server::from_tcp(listener)
+                    .unwrap()
+                    .serve(router.into_make_service())
+                    .await
+                    .unwrap();
+            });
+    });
+
+    url
+}
+
+// Run the binary against a node and faucet both served at `url`, with a clean environment, a
+// throwaway home and keystore, and a fixed keystore password
+pub fn run(url: &str, chain_id: Option<u8>, home: &Path, args: &[&str]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_aptos_client_test"));
+    if let Some(chain_id) = chain_id {
+        command.args(["--chain-id", &chain_id.to_string()]);
+    }
+    command
+        .env_clear()
+        .env("HOME", home)
+        .env("APTOS_KEYSTORE_PASSWORD", PASSWORD)
+        .arg("--keystore")
+        .arg(home.join("keystore"))
+        .args(["--network", "custom", "--node-url", url])
+        .args(["--faucet-url", url])
+        .args(args)
+        .output()
+        .expect("failed to run binary")
+}
+
+pub fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "command failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+// Addresses appear both short (0x1) and zero padded, so compare them without leading zeros
+pub fn normalize(address: &str) -> String {
+    let hex = address.trim_start_matches("0x").trim_start_matches('0');
+    format!("0x{}", if hex.is_empty() { "0" } else { hex }).to_lowercase()
+}
+
+fn parse_address(address: &str) -> Option<AccountAddress> {
+    AccountAddress::from_hex_literal(&normalize(address)).ok()
+}
+
+async fn handle(
+    Extension(ledger): Extension<Arc<Mutex<Ledger>>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let path = percent_decode(uri.path());
+    let path = path.strip_prefix("/v1").unwrap_or(&path);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let query = parse_query(uri.query().unwrap_or_default());
+    let mut ledger = ledger.lock().unwrap();
+
+    match (method, segments.as_slice()) {
+        (Method::GET, [""]) => respond(&ledger, StatusCode::OK, ledger_info(&ledger)),
+        (Method::GET, ["accounts", address]) => match account_info(&ledger, address) {
+            Some(account) => respond(&ledger, StatusCode::OK, account),
+            None => not_found(&ledger, "Account not found"),
+        },
+        (Method::GET, ["accounts", address, "resources"]) => {
+            match parse_address(address).and_then(|address| ledger.accounts.get(&address)) {
+                Some(account) => {
+                    let resources = account
+                        .coins
+                        .keys()
+                        .filter_map(|coin_type| {
+                            coin_store(&ledger, address, &coin_store_type(coin_type))
+                        })
+                        .collect();
+                    respond(&ledger, StatusCode::OK, Value::Array(resources))
+                }
+                None => not_found(&ledger, "Account not found"),
+            }
+        }
+        (Method::GET, ["accounts", address, "resource", resource_type]) => {
+            match coin_store(&ledger, address, resource_type) {
+                Some(resource) => respond(&ledger, StatusCode::OK, resource),
+                None => not_found(&ledger, "Resource not found"),
+            }
+        }
+        (Method::GET, ["accounts", address, "transactions"]) => {
+            let transactions = account_transactions(&ledger, address, &query);
+            respond(&ledger, StatusCode::OK, Value::Array(transactions))
+        }
+        (Method::GET, ["transactions", "by_hash", hash])
+        | (Method::GET, ["transactions", hash]) => match transaction_by_hash(&ledger, hash) {
+            Some(transaction) => respond(&ledger, StatusCode::OK, transaction),
+            None => not_found(&ledger, "Transaction not found"),
+        },
+        (Method::POST, ["transactions"]) => submit(&mut ledger, &body),
+        (Method::POST, ["transactions", "simulate"]) => simulate(&ledger, &body),
+        (Method::POST, ["mint"]) => mint(&mut ledger, &query),
+        _ => not_found(&ledger, "Unknown endpoint"),
+    }
+}
+
+fn ledger_info(ledger: &Ledger) -> Value {
+    json!({
+        "chain_id": ledger.chain_id,
+        "epoch": "1",
+        "ledger_version": ledger.version().to_string(),
+        "oldest_ledger_version": "0",
+        "ledger_timestamp": timestamp_usecs().to_string(),
+        "block_height": ledger.version().to_string(),
+        "oldest_block_height": "0",
+        "node_role": "full_node",
+    })
+}
+
+fn account_info(ledger: &Ledger, address: &str) -> Option<Value> {
+    let address = parse_address(address)?;
+    let account = ledger.accounts.get(&address)?;
+
+    Some(json!({
+        "sequence_number": account.sequence_number.to_string(),
+        "authentication_key": format!("0x{}", hex::encode(address.into_bytes())),
+    }))
+}
+
+fn coin_store_type(coin_type: &str) -> String {
+    format!("0x1::coin::CoinStore<{}>", coin_type)
+}
+
+fn coin_store(ledger: &Ledger, address: &str, resource_type: &str) -> Option<Value> {
+    let coin_type = resource_type
+        .strip_prefix("0x1::coin::CoinStore<")?
+        .strip_suffix('>')?;
+    let address = parse_address(address)?;
+    let account = ledger.accounts.get(&address)?;
+    let value = account.coins.get(coin_type)?;
+
+    Some(coin_store_json(address, account, coin_type, *value))
+}
+
+fn coin_store_json(
+    address: AccountAddress,
+    account: &Account,
+    coin_type: &str,
+    value: u64,
+) -> Value {
+    let address = address.to_hex_literal();
+    json!({
+        "type": coin_store_type(coin_type),
+        "data": {
+            "coin": { "value": value.to_string() },
+            "frozen": false,
+            "deposit_events": {
+                "counter": account.deposit_events.to_string(),
+                "guid": { "id": { "addr": address, "creation_num": "2" } },
+            },
+            "withdraw_events": {
+                "counter": account.withdraw_events.to_string(),
+                "guid": { "id": { "addr": address, "creation_num": "3" } },
+            },
+        },
+    })
+}
+
+// User transactions sent by an account, from sequence number `start` on
+fn account_transactions(
+    ledger: &Ledger,
+    address: &str,
+    query: &HashMap<String, String>,
+) -> Vec<Value> {
+    let sender = match parse_address(address) {
+        Some(address) => address.to_hex_literal(),
+        None => return Vec::new(),
+    };
+    let start: u64 = query
+        .get("start")
+        .and_then(|start| start.parse().ok())
+        .unwrap_or(0);
+    let limit: usize = query
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(25);
+
+    ledger
+        .transactions
+        .iter()
+        .filter(|transaction| transaction["sender"] == sender.as_str())
+        .filter(|transaction| {
+            let sequence_number = transaction["sequence_number"].as_str().unwrap_or_default();
+            sequence_number
+                .parse::<u64>()
+                .map_or(false, |number| number >= start)
+        })
+        .take(limit)
+        .cloned()
+        .collect()
+}
+
+fn transaction_by_hash(ledger: &Ledger, hash: &str) -> Option<Value> {
+    let hash = hash.trim_start_matches("0x").to_lowercase();
+    ledger
+        .transactions
+        .iter()
+        .find(|transaction| {
+            transaction["hash"]
+                .as_str()
+                .map(|hash| hash.trim_start_matches("0x"))
+                == Some(hash.as_str())
+        })
+        .cloned()
+}
+
+// Accept a BCS signed transaction. It is executed straight away when its sequence number is
+// next for the sender, or parked until the transactions before it arrive
+fn submit(ledger: &mut Ledger, body: &[u8]) -> Response {
+    let signed: SignedTransaction = match bcs::from_bytes(body) {
+        Ok(signed) => signed,
+        Err(err) => return bad_request(ledger, &format!("Invalid BCS transaction: {}", err)),
+    };
+    if signed.clone().check_signature().is_err() {
+        return vm_error(ledger, VmStatus::INVALID_SIGNATURE);
+    }
+    if let Err(status) = validate(ledger, &signed) {
+        return vm_error(ledger, status);
+    }
+
+    let pending = pending_json(&signed);
+    let sender = signed.sender();
+    let sequence_number = signed.sequence_number();
+    ledger.parked.insert((sender, sequence_number), signed);
+    commit_ready(ledger, sender);
+
+    respond(ledger, StatusCode::ACCEPTED, pending)
+}
+
+// Execute the sender's parked transactions for as long as the next one is there
+fn commit_ready(ledger: &mut Ledger, sender: AccountAddress) {
+    loop {
+        let next = ledger.accounts[&sender].sequence_number;
+        let signed = match ledger.parked.remove(&(sender, next)) {
+            Some(signed) => signed,
+            None => return,
+        };
+        if signed.expiration_timestamp_secs() <= now_secs() {
+            // Expired while waiting, so it never runs and the gap stays open
+            return;
+        }
+
+        let version = ledger.version();
+        let transaction = execute(&mut ledger.accounts, &signed, version);
+        ledger.transactions.push(transaction);
+    }
+}
+
+// Nodes only simulate transactions that could not also be submitted, so the signature must
+// not verify
+fn simulate(ledger: &Ledger, body: &[u8]) -> Response {
+    let signed: SignedTransaction = match bcs::from_bytes(body) {
+        Ok(signed) => signed,
+        Err(err) => return bad_request(ledger, &format!("Invalid BCS transaction: {}", err)),
+    };
+    if signed.clone().check_signature().is_ok() {
+        return bad_request(
+            ledger,
+            "Simulated transactions must not have a valid signature",
+        );
+    }
+    if let Err(status) = validate(ledger, &signed) {
+        return vm_error(ledger, status);
+    }
+
+    let mut accounts = ledger.accounts.clone();
+    let transaction = execute(&mut accounts, &signed, ledger.version());
+    respond(ledger, StatusCode::OK, json!([transaction]))
+}
+
+// Mint coins to an account, creating it first if needed, the way the devnet faucet does. The
+// mint is a transaction signed by the faucet's own account
+fn mint(ledger: &mut Ledger, query: &HashMap<String, String>) -> Response {
+    let address = query
+        .get("auth_key")
+        .or_else(|| query.get("address"))
+        .and_then(|address| parse_address(address));
+    let amount = query.get("amount").and_then(|amount| amount.parse().ok());
+    let (address, amount) = match (address, amount) {
+        (Some(address), Some(amount)) => (address, amount),
+        _ => return bad_request(ledger, "mint needs an auth_key and an amount"),
+    };
+
+    let builder = TransactionBuilder::new(
+        aptos_stdlib::aptos_coin_mint(address, amount),
+        now_secs() + 30,
+        ChainId::new(ledger.chain_id),
+    )
+    .sender(ledger.faucet.address())
+    .sequence_number(ledger.faucet.sequence_number())
+    .max_gas_amount(1_000)
+    .gas_unit_price(0);
+    let signed = ledger.faucet.sign_with_transaction_builder(builder);
+
+    let version = ledger.version();
+    let transaction = execute(&mut ledger.accounts, &signed, version);
+    ledger.transactions.push(transaction);
+
+    match query.get("return_txns").map(String::as_str) {
+        Some("true") => {
+            let encoded = hex::encode(bcs::to_bytes(&vec![signed]).unwrap());
+            (StatusCode::OK, encoded).into_response()
+        }
+        _ => {
+            let hash = signed.committed_hash().to_hex();
+            (StatusCode::OK, Json(json!([hash]))).into_response()
+        }
+    }
+}
+
+// The checks a node makes before a transaction is allowed into the mempool
+fn validate(ledger: &Ledger, signed: &SignedTransaction) -> Result<(), VmStatus> {
+    if signed.chain_id() != ChainId::new(ledger.chain_id) {
+        return Err(VmStatus::BAD_CHAIN_ID);
+    }
+    let account = ledger
+        .accounts
+        .get(&signed.sender())
+        .ok_or(VmStatus::SENDING_ACCOUNT_DOES_NOT_EXIST)?;
+    if signed.sequence_number() < account.sequence_number {
+        return Err(VmStatus::SEQUENCE_NUMBER_TOO_OLD);
+    }
+    if signed.sequence_number() > account.sequence_number + MAX_SEQUENCE_NUMBER_GAP {
+        return Err(VmStatus::SEQUENCE_NUMBER_TOO_NEW);
+    }
+    if signed.expiration_timestamp_secs() <= now_secs() {
+        return Err(VmStatus::TRANSACTION_EXPIRED);
+    }
+    let max_fee = signed.max_gas_amount() * signed.gas_unit_price();
+    if account.coins.get(APTOS_COIN).copied().unwrap_or(0) < max_fee {
+        return Err(VmStatus::INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE);
+    }
+
+    Ok(())
+}
+
+// What running a transaction did: its status, the events it emitted and the CoinStores it
+// wrote
+struct Effects {
+    vm_status: String,
+    events: Vec<Value>,
+    changes: Vec<(AccountAddress, String)>,
+}
+
+impl Effects {
+    fn aborted(vm_status: &str) -> Self {
+        Self {
+            vm_status: vm_status.to_string(),
+            events: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+}
+
+// Run a validated transaction against `accounts` and render it as a committed transaction at
+// `version`. Gas is charged and the sequence number used whether or not the call aborts
+fn execute(
+    accounts: &mut HashMap<AccountAddress, Account>,
+    signed: &SignedTransaction,
+    version: u64,
+) -> Value {
+    let sender = signed.sender();
+    let gas_used = match signed.gas_unit_price() {
+        0 => 0,
+        _ => GAS_USED,
+    };
+    {
+        let account = accounts.get_mut(&sender).unwrap();
+        account.sequence_number += 1;
+        let balance = account.coins.entry(APTOS_COIN.to_string()).or_default();
+        *balance = balance.saturating_sub(gas_used * signed.gas_unit_price());
+    }
+
+    let effects = match call(signed) {
+        Some(("coin", "transfer", coin_type, recipient, amount)) => {
+            transfer(accounts, sender, &coin_type, recipient, amount)
+        }
+        Some(("aptos_coin", "mint", _, recipient, amount)) => mint_to(accounts, recipient, amount),
+        _ => Effects::aborted(
+            "Mock node only executes 0x1::coin::transfer and 0x1::aptos_coin::mint",
+        ),
+    };
+
+    let mut changes: Vec<Value> = Vec::new();
+    let mut written = effects.changes.clone();
+    if !written.contains(&(sender, APTOS_COIN.to_string())) {
+        written.push((sender, APTOS_COIN.to_string()));
+    }
+    for (address, coin_type) in written {
+        let account = &accounts[&address];
+        if let Some(value) = account.coins.get(&coin_type) {
+            changes.push(json!({
+                "type": "write_resource",
+                "address": address.to_hex_literal(),
+                "state_key_hash": ZERO_HASH,
+                "data": coin_store_json(address, account, &coin_type, *value),
+            }));
+        }
+    }
+
+    let mut transaction = pending_json(signed);
+    let fields = transaction.as_object_mut().unwrap();
+    fields.insert("type".to_string(), json!("user_transaction"));
+    fields.insert("version".to_string(), json!(version.to_string()));
+    fields.insert("state_change_hash".to_string(), json!(ZERO_HASH));
+    fields.insert("event_root_hash".to_string(), json!(ZERO_HASH));
+    fields.insert("state_checkpoint_hash".to_string(), Value::Null);
+    fields.insert("accumulator_root_hash".to_string(), json!(ZERO_HASH));
+    fields.insert("gas_used".to_string(), json!(gas_used.to_string()));
+    fields.insert(
+        "success".to_string(),
+        json!(effects.vm_status == "Executed successfully"),
+    );
+    fields.insert("vm_status".to_string(), json!(effects.vm_status));
+    fields.insert("changes".to_string(), Value::Array(changes));
+    fields.insert("events".to_string(), Value::Array(effects.events));
+    fields.insert(
+        "timestamp".to_string(),
+        json!(timestamp_usecs().to_string()),
+    );
+    transaction
+}
+
+// The module, function, type argument and (recipient, amount) arguments of the entry
+// functions the mock understands
+fn call(signed: &SignedTransaction) -> Option<(&str, &str, String, AccountAddress, u64)> {
+    let function = match signed.payload() {
+        TransactionPayload::EntryFunction(function) => function,
+        _ => return None,
+    };
+    let args = function.args();
+    let recipient: AccountAddress = bcs::from_bytes(args.get(0)?).ok()?;
+    let amount: u64 = bcs::from_bytes(args.get(1)?).ok()?;
+    let coin_type = function
+        .ty_args()
+        .first()
+        .map(|coin_type| coin_type.to_string())
+        .unwrap_or_else(|| APTOS_COIN.to_string());
+
+    Some((
+        function.module().name().as_str(),
+        function.function().as_str(),
+        coin_type,
+        recipient,
+        amount,
+    ))
+}
+
+fn transfer(
+    accounts: &mut HashMap<AccountAddress, Account>,
+    sender: AccountAddress,
+    coin_type: &str,
+    recipient: AccountAddress,
+    amount: u64,
+) -> Effects {
+    let balance = accounts[&sender].coins.get(coin_type).copied();
+    match balance {
+        Some(balance) if balance >= amount => {}
+        Some(_) => {
+            return Effects::aborted(
+                "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE(0x10006): Not enough coins to complete transaction",
+            )
+        }
+        None => {
+            return Effects::aborted(
+                "Move abort in 0x1::coin: ECOIN_STORE_NOT_PUBLISHED(0x60005): Account hasn't registered `CoinStore` for `CoinType`",
+            )
+        }
+    }
+    let registered = accounts
+        .get(&recipient)
+        .map_or(false, |account| account.coins.contains_key(coin_type));
+    if !registered {
+        return Effects::aborted(
+            "Move abort in 0x1::coin: ECOIN_STORE_NOT_PUBLISHED(0x60005): Account hasn't registered `CoinStore` for `CoinType`",
+        );
+    }
+
+    let withdraw = {
+        let account = accounts.get_mut(&sender).unwrap();
+        *account.coins.get_mut(coin_type).unwrap() -= amount;
+        account.withdraw_events += 1;
+        coin_event(
+            sender,
+            "0x1::coin::WithdrawEvent",
+            3,
+            account.withdraw_events - 1,
+            amount,
+        )
+    };
+    let deposit = deposit(accounts, recipient, coin_type, amount);
+
+    Effects {
+        vm_status: "Executed successfully".to_string(),
+        events: vec![withdraw, deposit],
+        changes: vec![
+            (sender, coin_type.to_string()),
+            (recipient, coin_type.to_string()),
+        ],
+    }
+}
+
+fn mint_to(
+    accounts: &mut HashMap<AccountAddress, Account>,
+    recipient: AccountAddress,
+    amount: u64,
+) -> Effects {
+    accounts
+        .entry(recipient)
+        .or_default()
+        .coins
+        .entry(APTOS_COIN.to_string())
+        .or_default();
+    let deposit = deposit(accounts, recipient, APTOS_COIN, amount);
+
+    Effects {
+        vm_status: "Executed successfully".to_string(),
+        events: vec![deposit],
+        changes: vec![(recipient, APTOS_COIN.to_string())],
+    }
+}
+
+fn deposit(
+    accounts: &mut HashMap<AccountAddress, Account>,
+    recipient: AccountAddress,
+    coin_type: &str,
+    amount: u64,
+) -> Value {
+    let account = accounts.get_mut(&recipient).unwrap();
+    *account.coins.get_mut(coin_type).unwrap() += amount;
+    account.deposit_events += 1;
+    coin_event(
+        recipient,
+        "0x1::coin::DepositEvent",
+        2,
+        account.deposit_events - 1,
+        amount,
+    )
+}
+
+// Events carry both the old event key (creation number then address) and the newer GUID
+fn coin_event(
+    address: AccountAddress,
+    event_type: &str,
+    creation_number: u64,
+    sequence_number: u64,
+    amount: u64,
+) -> Value {
+    json!({
+        "key": format!(
+            "0x{}{}",
+            hex::encode(creation_number.to_le_bytes()),
+            hex::encode(address.into_bytes())
+        ),
+        "guid": {
+            "creation_number": creation_number.to_string(),
+            "account_address": address.to_hex_literal(),
+        },
+        "sequence_number": sequence_number.to_string(),
+        "type": event_type,
+        "data": { "amount": amount.to_string() },
+    })
+}
+
+// The request half of a transaction, which is all a pending transaction has
+fn pending_json(signed: &SignedTransaction) -> Value {
+    json!({
+        "type": "pending_transaction",
+        "hash": signed.clone().committed_hash().to_hex_literal(),
+        "sender": signed.sender().to_hex_literal(),
+        "sequence_number": signed.sequence_number().to_string(),
+        "max_gas_amount": signed.max_gas_amount().to_string(),
+        "gas_unit_price": signed.gas_unit_price().to_string(),
+        "expiration_timestamp_secs": signed.expiration_timestamp_secs().to_string(),
+        "payload": payload_json(signed),
+        "signature": signature_json(signed),
+    })
+}
+
+fn payload_json(signed: &SignedTransaction) -> Value {
+    let function = match signed.payload() {
+        TransactionPayload::EntryFunction(function) => function,
+        _ => return json!({ "type": "unsupported_payload" }),
+    };
+    let arguments: Vec<Value> = match call(signed) {
+        Some((_, _, _, recipient, amount)) => {
+            vec![json!(recipient.to_hex_literal()), json!(amount.to_string())]
+        }
+        None => function
+            .args()
+            .iter()
+            .map(|arg| json!(format!("0x{}", hex::encode(arg))))
+            .collect(),
+    };
+
+    json!({
+        "type": "entry_function_payload",
+        "function": format!(
+            "{}::{}::{}",
+            function.module().address().to_hex_literal(),
+            function.module().name(),
+            function.function()
+        ),
+        "type_arguments": function.ty_args().iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "arguments": arguments,
+    })
+}
+
+fn signature_json(signed: &SignedTransaction) -> Value {
+    match signed.authenticator() {
+        TransactionAuthenticator::Ed25519 {
+            public_key,
+            signature,
+        } => json!({
+            "type": "ed25519_signature",
+            "public_key": format!("0x{}", hex::encode(public_key.to_bytes())),
+            "signature": format!("0x{}", hex::encode(signature.to_bytes())),
+        }),
+        _ => json!({ "type": "unsupported_signature" }),
+    }
+}
+
+fn not_found(ledger: &Ledger, message: &str) -> Response {
+    error(
+        ledger,
+        StatusCode::NOT_FOUND,
+        message,
+        "resource_not_found",
+        None,
+    )
+}
+
+fn bad_request(ledger: &Ledger, message: &str) -> Response {
+    error(
+        ledger,
+        StatusCode::BAD_REQUEST,
+        message,
+        "invalid_input",
+        None,
+    )
+}
+
+// A transaction rejected before it reached the mempool, reported as nodes do
+fn vm_error(ledger: &Ledger, status: VmStatus) -> Response {
+    error(
+        ledger,
+        StatusCode::BAD_REQUEST,
+        &format!("Invalid transaction: Type: Validation Code: {:?}", status),
+        "vm_error",
+        Some(status as u64),
+    )
+}
+
+fn error(
+    ledger: &Ledger,
+    status: StatusCode,
+    message: &str,
+    error_code: &str,
+    vm_error_code: Option<u64>,
+) -> Response {
+    respond(
+        ledger,
+        status,
+        json!({
+            "code": status.as_u16(),
+            "message": message,
+            "error_code": error_code,
+            "vm_error_code": vm_error_code,
+            "aptos_ledger_version": ledger.version().to_string(),
+        }),
+    )
+}
+
+// Every response carries the ledger state headers the rest client reads
+fn respond(ledger: &Ledger, status: StatusCode, body: Value) -> Response {
+    let mut headers = HeaderMap::new();
+    let version = HeaderValue::from_str(&ledger.version().to_string()).unwrap();
+    headers.insert(
+        "X-Aptos-Chain-Id",
+        HeaderValue::from(u16::from(ledger.chain_id)),
+    );
+    headers.insert("X-Aptos-Epoch", HeaderValue::from_static("1"));
+    headers.insert("X-Aptos-Ledger-Version", version.clone());
+    headers.insert(
+        "X-Aptos-Ledger-Oldest-Version",
+        HeaderValue::from_static("0"),
+    );
+    headers.insert("X-Aptos-Block-Height", version);
+    headers.insert("X-Aptos-Oldest-Block-Height", HeaderValue::from_static("0"));
+    headers.insert(
+        "X-Aptos-Ledger-TimestampUsec",
+        HeaderValue::from_str(&timestamp_usecs().to_string()).unwrap(),
+    );
+
+    (status, headers, Json(body)).into_response()
+}
+
+fn timestamp_usecs() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), percent_decode(value)))
+        .collect()
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(&path[i + 1..i + 3], 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(decoded).unwrap()
+}
@@ -0,0 +1,83 @@
+// The balance conservation check on its own, with hand written balances and receipts
+use aptos_client_test::config::APTOS_COIN_TYPE;
+use aptos_client_test::conservation::{Balances, Conservation};
+use aptos_client_test::receipt::Receipt;
+use aptos_sdk::types::account_address::AccountAddress;
+
+const FEE: u64 = 10;
+
+fn receipt(success: bool) -> Receipt {
+    Receipt {
+        hash: "0xabc".to_string(),
+        version: 7,
+        success,
+        vm_status: "Executed successfully".to_string(),
+        gas_used: FEE,
+        gas_unit_price: 1,
+        events: Vec::new(),
+    }
+}
+
+fn check(before: (u64, u64), after: (u64, u64), success: bool, coin_type: &str) -> Conservation {
+    Conservation::new(
+        Balances {
+            sender: before.0,
+            receiver: before.1,
+        },
+        Balances {
+            sender: after.0,
+            receiver: after.1,
+        },
+        1_000,
+        &receipt(success),
+        AccountAddress::from_hex_literal("0xa").unwrap(),
+        AccountAddress::from_hex_literal("0xb").unwrap(),
+        coin_type,
+    )
+}
+
+#[test]
+fn transfer_that_moves_amount_and_fee_holds() {
+    let conservation = check((20_000, 0), (18_990, 1_000), true, APTOS_COIN_TYPE);
+
+    assert!(conservation.holds());
+    assert_eq!(conservation.to_string(), "balances conserved");
+}
+
+#[test]
+fn sender_charged_too_much_is_reported_with_a_diff() {
+    let conservation = check((20_000, 0), (18_980, 1_000), true, APTOS_COIN_TYPE);
+
+    assert!(!conservation.holds());
+    assert_eq!(
+        conservation.to_string(),
+        "balances not conserved for a transfer of 1000 with a fee of 10\n  \
+         sender balance: expected 18990 (20000 - 1000 - 10), actual 18980 (off by -10)"
+    );
+}
+
+#[test]
+fn receiver_shortfall_is_reported() {
+    let conservation = check((20_000, 5), (18_990, 1_000), true, APTOS_COIN_TYPE);
+    let mismatches = conservation.mismatches();
+
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(
+        mismatches[0].to_string(),
+        "receiver balance: expected 1005 (5 + 1000), actual 1000 (off by -5)"
+    );
+}
+
+#[test]
+fn aborted_transfer_only_costs_the_fee() {
+    assert!(check((20_000, 0), (19_990, 0), false, APTOS_COIN_TYPE).holds());
+    assert!(!check((20_000, 0), (18_990, 1_000), false, APTOS_COIN_TYPE).holds());
+}
+
+#[test]
+fn fee_is_not_taken_from_other_coins() {
+    let coin_type = "0x1234::moon_coin::MoonCoin";
+
+    assert!(check((20_000, 0), (19_000, 1_000), true, coin_type).holds());
+    assert!(!check((20_000, 0), (18_990, 1_000), true, coin_type).holds());
+}
@@ -0,0 +1,112 @@
+// The Alice to Bob walkthrough, run end to end against the mock node and faucet
+mod common;
+
+use common::{stdout, MockNode, GAS_USED};
+
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+
+fn run_demo(node: &MockNode, home: &std::path::Path) -> String {
+    let mut args = GAS.to_vec();
+    args.push("demo");
+    stdout(&node.run(home, &args))
+}
+
+// The demo prints "Alice: 0x..." and "Bob: 0x..." before it touches the chain
+fn address_of<'a>(output: &'a str, name: &str) -> &'a str {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+        .unwrap_or_else(|| panic!("no address for {} in:\n{}", name, output))
+}
+
+#[test]
+fn demo_funds_alice_and_pays_bob_twice() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+
+    let output = run_demo(&node, home.path());
+    let alice = address_of(&output, "Alice");
+    let bob = address_of(&output, "Bob");
+
+    assert_eq!(node.balance(bob), 2_000);
+    assert_eq!(node.balance(alice), 20_000 - 2 * (1_000 + GAS_USED));
+    assert_eq!(node.sequence_number(alice), Some(2));
+    assert_eq!(node.sequence_number(bob), Some(0));
+
+    let transfers = node.transactions_from(alice);
+    assert_eq!(transfers.len(), 2);
+    assert!(transfers.iter().all(|transfer| transfer["success"] == true
+        && transfer["payload"]["function"] == "0x1::coin::transfer"));
+
+    for banner in [
+        "Initial balances",
+        "Intermediate balances",
+        "Final balances",
+    ] {
+        assert!(
+            output.contains(banner),
+            "missing {} in:\n{}",
+            banner,
+            output
+        );
+    }
+}
+
+#[test]
+fn second_demo_run_reuses_accounts_without_refunding() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+
+    let first = run_demo(&node, home.path());
+    let second = run_demo(&node, home.path());
+    let alice = address_of(&second, "Alice");
+    assert_eq!(alice, address_of(&first, "Alice"));
+
+    // The saved sequence number carries over, so the second run's transfers commit too
+    assert_eq!(node.balance(address_of(&second, "Bob")), 4_000);
+    assert_eq!(node.balance(alice), 20_000 - 4 * (1_000 + GAS_USED));
+    assert_eq!(node.sequence_number(alice), Some(4));
+}
+
+#[test]
+fn demo_ndjson_reports_every_step() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+
+    let mut args = GAS.to_vec();
+    args.extend(["demo", "--output", "ndjson"]);
+    let output = stdout(&node.run(home.path(), &args));
+    let records: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    let kinds: Vec<(&str, &str)> = records
+        .iter()
+        .map(|record| {
+            (
+                record["record"].as_str().unwrap(),
+                record["stage"].as_str().unwrap_or("-"),
+            )
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("fund", "-"),
+            ("create", "-"),
+            ("balance", "initial"),
+            ("balance", "initial"),
+            ("transfer", "-"),
+            ("balance", "intermediate"),
+            ("balance", "intermediate"),
+            ("transfer", "-"),
+            ("balance", "final"),
+            ("balance", "final"),
+        ]
+    );
+    assert_eq!(records[4]["fee"], GAS_USED);
+    assert_eq!(records[9]["label"], "bob");
+    assert_eq!(records[9]["amount"], 2_000);
+    assert!(records.iter().all(|record| record["error"].is_null()));
+}
@@ -0,0 +1,203 @@
+// Error categories, decoded aborts and the exit codes the binary ends with for each
+mod common;
+
+use anyhow::anyhow;
+use aptos_client_test::error::{ClientError, MoveAbort};
+use aptos_client_test::retry::RetryPolicy;
+use aptos_sdk::types::LocalAccount;
+use axum::http::Method;
+use common::flaky::{Fault, Flaky, When};
+use common::{stderr, MockNode, CHAIN_ID};
+use std::process::Output;
+
+const ADDRESS: &str = "0xa11ce";
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+
+fn transfer(node: &MockNode, amount: &str) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    let mut args = GAS.to_vec();
+    args.extend(["transfer", "--from", SENDER_KEY, "--to", RECIPIENT]);
+    args.extend(["--amount", amount]);
+
+    node.run(home.path(), &args)
+}
+
+#[test]
+fn insufficient_balance_abort_is_decoded() {
+    let abort = MoveAbort::parse(
+        "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE(0x10006): Not enough coins to complete transaction",
+    )
+    .unwrap();
+
+    assert_eq!(abort.module, "0x1::coin");
+    assert_eq!(abort.name.as_deref(), Some("EINSUFFICIENT_BALANCE"));
+    assert_eq!(abort.code, 0x10006);
+    assert_eq!(
+        abort.to_string(),
+        "Move abort in 0x1::coin: EINSUFFICIENT_BALANCE (0x10006): the sender does not hold enough of the coin for this transfer"
+    );
+    assert!(matches!(
+        abort.into_error(),
+        ClientError::InsufficientBalance(_)
+    ));
+}
+
+#[test]
+fn missing_coin_store_means_the_account_is_not_found() {
+    let error = ClientError::from_vm_status(
+        "Move abort in 0x0000000000000000000000000000000000000000000000000000000000000001::coin: ECOIN_STORE_NOT_PUBLISHED(0x60005): Account hasn't registered `CoinStore` for `CoinType`",
+    );
+
+    assert_eq!(error.exit_code(), 8);
+    assert!(
+        error
+            .to_string()
+            .contains("0x1::coin: ECOIN_STORE_NOT_PUBLISHED"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn unknown_aborts_are_described_by_their_category() {
+    let abort = MoveAbort::parse("Move abort in 0xcafe::market: 0x3000c").unwrap();
+
+    assert_eq!(abort.name, None);
+    assert_eq!(abort.reason, "invalid state, reason 12");
+    assert_eq!(abort.into_error().exit_code(), 9);
+    assert_eq!(
+        ClientError::from_vm_status("Out of gas"),
+        ClientError::ExecutionFailed("Out of gas".to_string())
+    );
+}
+
+#[test]
+fn node_rejections_are_classified_from_their_text() {
+    let classify = |message: &str| {
+        let err = anyhow!(message.to_string()).context("Failed to transfer coins");
+        ClientError::classify(&err, &RetryPolicy::default()).map(|error| error.exit_code())
+    };
+
+    assert_eq!(
+        classify("Invalid transaction: Type: Validation Code: SEQUENCE_NUMBER_TOO_OLD"),
+        Some(6)
+    );
+    assert_eq!(
+        classify("Invalid transaction: Type: Validation Code: TRANSACTION_EXPIRED"),
+        Some(7)
+    );
+    assert_eq!(
+        classify(
+            "Invalid transaction: Type: Validation Code: INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE"
+        ),
+        Some(5)
+    );
+    assert_eq!(
+        classify("HTTP error 404 Not Found: Account not found"),
+        Some(8)
+    );
+    assert_eq!(classify("HTTP error 503 Service Unavailable"), Some(3));
+    assert_eq!(classify("Private key is not valid hex"), None);
+}
+
+#[test]
+fn network_errors_follow_the_configured_retry_policy() {
+    let err = anyhow!("HTTP error 503 Service Unavailable").context("Could not fetch account");
+    let only_rate_limits = RetryPolicy {
+        retryable_status_codes: vec![429],
+        ..RetryPolicy::default()
+    };
+
+    assert_eq!(
+        ClientError::classify(&err, &RetryPolicy::default()).map(|error| error.exit_code()),
+        Some(3)
+    );
+    assert_eq!(ClientError::classify(&err, &only_rate_limits), None);
+}
+
+#[test]
+fn typed_errors_win_over_their_text() {
+    let err = anyhow::Error::new(ClientError::faucet(anyhow!(
+        "HTTP error 503 Service Unavailable"
+    )))
+    .context("Failed to fund 0xa11ce");
+
+    assert_eq!(
+        ClientError::classify(&err, &RetryPolicy::default())
+            .unwrap()
+            .exit_code(),
+        4
+    );
+}
+
+#[test]
+fn transfer_beyond_the_balance_exits_with_insufficient_balance() {
+    let node = MockNode::start();
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    node.set_balance(&sender.address().to_hex_literal(), 5_000);
+
+    let output = transfer(&node, "6000 octas");
+
+    assert_eq!(output.status.code(), Some(5), "{}", stderr(&output));
+    assert!(
+        stderr(&output)
+            .contains("holds 5000 octas of APT but needs 6000 to send and up to 1000 for gas"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(node.balance(RECIPIENT), 0);
+}
+
+#[test]
+fn transfer_from_an_unknown_account_exits_with_not_found() {
+    let node = MockNode::start();
+
+    let output = transfer(&node, "1000 octas");
+
+    assert_eq!(output.status.code(), Some(8), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("Reason: account not found"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn wrong_chain_exits_with_network_error() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+
+    let output = common::run(node.url(), Some(5), home.path(), &["balance", ADDRESS]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Reason: network error"));
+}
+
+#[test]
+fn faucet_refusal_exits_with_faucet_error() {
+    let node = MockNode::start();
+    let flaky = Flaky::start(
+        node.url(),
+        vec![Fault {
+            method: Method::POST,
+            path: "/mint",
+            status: 400,
+            times: 1,
+            when: When::Before,
+        }],
+    );
+    let home = tempfile::tempdir().unwrap();
+
+    let output = common::run(
+        flaky.url(),
+        Some(CHAIN_ID),
+        home.path(),
+        &["fund", ADDRESS, "5000 octas"],
+    );
+
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert!(stderr(&output).contains("faucet error"));
+    assert_eq!(node.balance(ADDRESS), 0);
+}
@@ -0,0 +1,195 @@
+// Explaining transactions from files, offline, and from their hashes on the mock node
+mod common;
+
+use aptos_client_test::offline::TransactionFile;
+use aptos_client_test::transaction;
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use common::{stdout, MockNode, APTOS_COIN, CHAIN_ID};
+use std::fs;
+use std::path::Path;
+
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+
+// Nothing listens on port 1, so explaining a file must not need the node
+const DEAD_NODE: &str = "http://127.0.0.1:1";
+
+fn sender() -> LocalAccount {
+    LocalAccount::from_private_key(SENDER_KEY, 0).unwrap()
+}
+
+fn description() -> String {
+    format!(
+        "send 1000 octas of AptosCoin from {} to 0xb0b",
+        sender().address().to_hex_literal()
+    )
+}
+
+// A signed transfer of 1000 octas to the recipient, as BCS
+fn signed_transfer() -> Vec<u8> {
+    let mut sender = sender();
+    let options = TransferOptions {
+        max_gas_amount: 1_000,
+        gas_unit_price: 1,
+        timeout_secs: 600,
+        coin_type: APTOS_COIN,
+    };
+    let builder = transaction::transfer_builder(
+        sender.address(),
+        sender.sequence_number(),
+        AccountAddress::from_hex_literal(RECIPIENT).unwrap(),
+        1_000,
+        &options,
+        CHAIN_ID,
+    )
+    .unwrap();
+
+    bcs::to_bytes(&sender.sign_with_transaction_builder(builder)).unwrap()
+}
+
+fn explain_file(home: &Path, contents: &[u8]) -> std::process::Output {
+    let path = home.join("transaction.bcs");
+    fs::write(&path, contents).unwrap();
+
+    common::run(DEAD_NODE, None, home, &["explain", path.to_str().unwrap()])
+}
+
+#[test]
+fn binary_bcs_is_explained_offline() {
+    let home = tempfile::tempdir().unwrap();
+
+    let output = stdout(&explain_file(home.path(), &signed_transfer()));
+
+    assert!(output.contains(&description()), "{}", output);
+    assert!(output.contains("Sequence number: 0"), "{}", output);
+    assert!(output.contains("Chain id: 4"), "{}", output);
+    assert!(
+        output.contains("Max gas: 1000 at 1 octas per unit, up to 1000 octas"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("Function: 0x1::coin::transfer<0x1::aptos_coin::AptosCoin>"),
+        "{}",
+        output
+    );
+    assert!(output.contains("Signature: valid"), "{}", output);
+}
+
+#[test]
+fn hex_encoded_bcs_is_explained() {
+    let home = tempfile::tempdir().unwrap();
+    let encoded = format!("0x{}\n", hex::encode(signed_transfer()));
+
+    let output = stdout(&explain_file(home.path(), encoded.as_bytes()));
+
+    assert!(output.contains(&description()), "{}", output);
+}
+
+#[test]
+fn tampered_transaction_has_an_invalid_signature() {
+    let home = tempfile::tempdir().unwrap();
+    let mut bytes = signed_transfer();
+    // The sequence number follows the 32 byte sender
+    bytes[32] = 7;
+
+    let output = stdout(&explain_file(home.path(), &bytes));
+
+    assert!(output.contains("Sequence number: 7"), "{}", output);
+    assert!(output.contains("Signature: INVALID"), "{}", output);
+}
+
+#[test]
+fn garbage_is_refused() {
+    let home = tempfile::tempdir().unwrap();
+
+    let output = explain_file(home.path(), b"not a transaction");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("neither a transaction file nor a signed transaction in BCS"));
+}
+
+#[test]
+fn committed_transfer_is_explained_by_hash() {
+    let node = MockNode::start();
+    let address = sender().address().to_hex_literal();
+    node.set_balance(&address, 10_000);
+    node.set_balance(RECIPIENT, 0);
+    let home = tempfile::tempdir().unwrap();
+    stdout(&node.run(
+        home.path(),
+        &[
+            "--max-gas-amount",
+            "1000",
+            "--gas-unit-price",
+            "1",
+            "transfer",
+            "--from",
+            SENDER_KEY,
+            "--to",
+            RECIPIENT,
+            "--amount",
+            "1000 octas",
+        ],
+    ));
+    let transactions = node.transactions_from(&address);
+    let hash = transactions[0]["hash"].as_str().unwrap();
+
+    let output = stdout(&node.run(home.path(), &["explain", hash]));
+
+    assert!(output.contains(&description()), "{}", output);
+    assert!(output.contains("Chain id: 4"), "{}", output);
+    assert!(
+        output.contains(&format!("Transaction: {}", hash)),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("VM status: Executed successfully"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn failed_transaction_is_explained_with_its_abort() {
+    let node = MockNode::start();
+    let address = sender().address().to_hex_literal();
+    node.set_balance(&address, 10_000);
+    let home = tempfile::tempdir().unwrap();
+    // The recipient has no account, and submitting a signed file makes no preflight checks
+    let signed = home.path().join("signed.json");
+    TransactionFile::signed(&bcs::from_bytes(&signed_transfer()).unwrap())
+        .unwrap()
+        .write(&signed)
+        .unwrap();
+    node.run(home.path(), &["submit", signed.to_str().unwrap()]);
+    let transactions = node.transactions_from(&address);
+    let hash = transactions[0]["hash"].as_str().unwrap();
+
+    let output = stdout(&node.run(home.path(), &["explain", hash]));
+
+    assert!(output.contains(&description()), "{}", output);
+    assert!(
+        output.contains(
+            "Reason: account not found: Move abort in 0x1::coin: ECOIN_STORE_NOT_PUBLISHED"
+        ),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn unknown_hash_fails() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let hash = format!("0x{}", "ab".repeat(32));
+
+    let output = node.run(home.path(), &["explain", &hash]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Could not fetch transaction"));
+}
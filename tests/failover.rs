@@ -0,0 +1,200 @@
+// Several nodes behind one command: health checks at startup and failover between requests.
+// Every mock node keeps its own ledger, so each test reads from just one of them
+mod common;
+
+use aptos_sdk::types::LocalAccount;
+use axum::http::Method;
+use common::flaky::{Fault, Flaky, When};
+use common::{stderr, stdout, MockNode, CHAIN_ID};
+use std::process::Output;
+
+const ADDRESS: &str = "0xa11ce";
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+
+// Nothing listens on port 1
+const DEAD_NODE: &str = "http://127.0.0.1:1";
+
+// `first` is the preferred node and also serves as the faucet
+fn run(first: &str, others: &[&str], args: &[&str]) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    let mut all = Vec::new();
+    for url in others {
+        all.extend(["--node-url", url]);
+    }
+    all.extend(args);
+
+    common::run(first, Some(CHAIN_ID), home.path(), &all)
+}
+
+#[test]
+fn unreachable_node_is_skipped() {
+    let node = MockNode::start();
+    node.set_balance(ADDRESS, 20_000);
+
+    let output = run(DEAD_NODE, &[node.url()], &["balance", ADDRESS]);
+
+    assert!(stdout(&output).contains("0.0002 APT"));
+    assert!(
+        stderr(&output).contains("Warning: skipping node http://127.0.0.1:1/: unreachable"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn lagging_node_is_skipped() {
+    let behind = MockNode::start();
+    let ahead = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    stdout(&ahead.run(home.path(), &["fund", ADDRESS, "5000 octas"]));
+
+    let output = run(
+        behind.url(),
+        &[ahead.url()],
+        &["--max-lag-versions", "0", "balance", ADDRESS],
+    );
+
+    assert!(stdout(&output).contains("0.00005 APT"));
+    assert!(
+        stderr(&output).contains("versions behind the newest node"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn lag_within_the_limit_keeps_the_preferred_node() {
+    let behind = MockNode::start();
+    behind.set_balance(ADDRESS, 7_000);
+    let ahead = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    stdout(&ahead.run(home.path(), &["fund", ADDRESS, "5000 octas"]));
+
+    let output = run(behind.url(), &[ahead.url()], &["balance", ADDRESS]);
+
+    assert!(stdout(&output).contains("0.00007 APT"));
+    assert!(!stderr(&output).contains("Warning"), "{}", stderr(&output));
+}
+
+#[test]
+fn no_healthy_node_lists_every_reason() {
+    let node = MockNode::start();
+
+    let output = run(
+        DEAD_NODE,
+        &[node.url()],
+        &["--chain-id", "5", "balance", ADDRESS],
+    );
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("No healthy node on the custom network"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("http://127.0.0.1:1/ unreachable"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("reports chain id 4 but chain id 5 is expected"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn nodes_on_another_chain_than_the_first_are_skipped() {
+    let first = MockNode::start();
+    first.set_balance(ADDRESS, 20_000);
+    let other = MockNode::start_on_chain(47);
+    other.set_balance(ADDRESS, 9_000);
+    let home = tempfile::tempdir().unwrap();
+
+    let output = common::run(
+        first.url(),
+        None,
+        home.path(),
+        &["--node-url", other.url(), "balance", ADDRESS],
+    );
+
+    assert!(stdout(&output).contains("0.0002 APT"));
+    assert!(
+        stderr(&output).contains("reports chain id 47 but chain id 4 is expected"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn failing_request_moves_to_the_next_node_without_retrying() {
+    let node = MockNode::start();
+    node.set_balance(ADDRESS, 20_000);
+    let flaky = Flaky::start(
+        node.url(),
+        vec![Fault {
+            method: Method::GET,
+            path: "/accounts/",
+            status: 503,
+            times: usize::MAX,
+            when: When::Before,
+        }],
+    );
+
+    // Both nodes pass the health check, which only reads ledger info
+    let output = run(
+        flaky.url(),
+        &[node.url()],
+        &["--max-attempts", "1", "balance", ADDRESS],
+    );
+
+    assert!(stdout(&output).contains("0.0002 APT"));
+    assert_eq!(flaky.bodies(&Method::GET, "/accounts/").len(), 1);
+}
+
+#[test]
+fn waiting_for_a_transfer_moves_to_the_next_node() {
+    let node = MockNode::start();
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    node.set_balance(&sender.address().to_hex_literal(), 20_000);
+    node.set_balance(RECIPIENT, 0);
+    // The transfer reaches the chain through the first node, which then stops answering
+    // about it
+    let flaky = Flaky::start(
+        node.url(),
+        vec![Fault {
+            method: Method::GET,
+            path: "/transactions/by_hash/",
+            status: 503,
+            times: usize::MAX,
+            when: When::Before,
+        }],
+    );
+
+    let output = run(
+        flaky.url(),
+        &[node.url()],
+        &[
+            "--max-attempts",
+            "1",
+            "--max-gas-amount",
+            "1000",
+            "--gas-unit-price",
+            "1",
+            "transfer",
+            "--from",
+            SENDER_KEY,
+            "--to",
+            RECIPIENT,
+            "--amount",
+            "1000 octas",
+        ],
+    );
+
+    assert!(stdout(&output).contains("Transaction: "));
+    assert_eq!(node.balance(RECIPIENT), 1_000);
+    assert_eq!(flaky.bodies(&Method::POST, "/transactions").len(), 1);
+}
@@ -0,0 +1,187 @@
+// Round trips through the encrypted keystore by driving the binary, all offline
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const PASSWORD: &str = "correct horse battery staple";
+const NEW_PASSWORD: &str = "tr0ub4dor&3";
+
+// A fixed key so the imported account's address is stable across runs
+const PRIVATE_KEY: &str = "0x4d2f1e4c3b2a19081f2e3d4c5b6a79887766554433221100ffeeddccbbaa9988";
+
+// With a clean environment and the temp dir as home, so neither a developer's config file nor
+// their APTOS_* variables change what the tests see
+fn run(keystore: &Path, password: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aptos_client_test"))
+        .env_clear()
+        .env("HOME", keystore.parent().unwrap())
+        .arg("--keystore")
+        .arg(keystore)
+        .args(args)
+        .env("APTOS_KEYSTORE_PASSWORD", password)
+        .env("APTOS_KEYSTORE_NEW_PASSWORD", NEW_PASSWORD)
+        .output()
+        .expect("failed to run binary")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "command failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn import_fixed_key(dir: &Path) {
+    let key_path = dir.join("key.txt");
+    fs::write(&key_path, PRIVATE_KEY).unwrap();
+    stdout(&run(
+        &dir.join("keystore"),
+        PASSWORD,
+        &[
+            "import",
+            "alice",
+            "--private-key-file",
+            key_path.to_str().unwrap(),
+        ],
+    ));
+}
+
+#[test]
+fn import_then_export_plaintext_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    import_fixed_key(dir.path());
+    let keystore = dir.path().join("keystore");
+
+    // The key must not be stored in plaintext
+    let stored = fs::read_to_string(keystore.join("alice.json")).unwrap();
+    assert!(!stored.contains(PRIVATE_KEY.trim_start_matches("0x")));
+
+    let exported = stdout(&run(
+        &keystore,
+        PASSWORD,
+        &["export", "alice", "--plaintext"],
+    ));
+    assert_eq!(exported.trim(), PRIVATE_KEY);
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    import_fixed_key(dir.path());
+
+    let output = run(
+        &dir.path().join("keystore"),
+        "not the password",
+        &["export", "alice", "--plaintext"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Wrong password"));
+}
+
+#[test]
+fn exported_key_file_imports_under_new_name() {
+    let dir = tempfile::tempdir().unwrap();
+    import_fixed_key(dir.path());
+    let keystore = dir.path().join("keystore");
+    let key_file = dir.path().join("alice.key.json");
+
+    stdout(&run(
+        &keystore,
+        PASSWORD,
+        &[
+            "export",
+            "alice",
+            "--output-file",
+            key_file.to_str().unwrap(),
+        ],
+    ));
+    stdout(&run(
+        &keystore,
+        PASSWORD,
+        &[
+            "import",
+            "alice-copy",
+            "--key-file",
+            key_file.to_str().unwrap(),
+        ],
+    ));
+
+    let exported = stdout(&run(
+        &keystore,
+        PASSWORD,
+        &["export", "alice-copy", "--plaintext"],
+    ));
+    assert_eq!(exported.trim(), PRIVATE_KEY);
+}
+
+#[test]
+fn change_password_re_encrypts_key() {
+    let dir = tempfile::tempdir().unwrap();
+    import_fixed_key(dir.path());
+    let keystore = dir.path().join("keystore");
+
+    stdout(&run(&keystore, PASSWORD, &["change-password", "alice"]));
+
+    assert!(
+        !run(&keystore, PASSWORD, &["export", "alice", "--plaintext"])
+            .status
+            .success()
+    );
+    let exported = stdout(&run(
+        &keystore,
+        NEW_PASSWORD,
+        &["export", "alice", "--plaintext"],
+    ));
+    assert_eq!(exported.trim(), PRIVATE_KEY);
+}
+
+#[cfg(unix)]
+#[test]
+fn plaintext_export_and_keystore_files_are_owner_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    import_fixed_key(dir.path());
+    let keystore = dir.path().join("keystore");
+    let exported = dir.path().join("alice.key");
+
+    stdout(&run(
+        &keystore,
+        PASSWORD,
+        &[
+            "export",
+            "alice",
+            "--plaintext",
+            "--output-file",
+            exported.to_str().unwrap(),
+        ],
+    ));
+
+    for path in [exported, keystore.join("alice.json")] {
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "{}", path.display());
+    }
+}
+
+#[test]
+fn rewriting_an_account_leaves_only_its_file() {
+    let dir = tempfile::tempdir().unwrap();
+    import_fixed_key(dir.path());
+    let keystore = dir.path().join("keystore");
+
+    stdout(&run(&keystore, PASSWORD, &["change-password", "alice"]));
+
+    let mut files: Vec<String> = fs::read_dir(&keystore)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["alice.json"]);
+    stdout(&run(
+        &keystore,
+        NEW_PASSWORD,
+        &["export", "alice", "--plaintext"],
+    ));
+}
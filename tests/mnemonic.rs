@@ -0,0 +1,100 @@
+// Accounts derived from a mnemonic must match the ones Aptos wallets and the Aptos CLI derive
+// from the same phrase, and scanning them must tell a missing account from a failing node
+mod common;
+
+use aptos_client_test::mnemonic;
+use axum::http::Method;
+use common::flaky::{Fault, Flaky, When};
+use common::{normalize, stdout, MockNode, CHAIN_ID};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The test wallet published with the Aptos TypeScript SDK, at m/44'/637'/0'/0'/0'. The address
+// is written zero padded as published, while the client prints it without leading zeros
+const PHRASE: &str =
+    "shoot island position soft burden budget tooth cruel issue economy destroy above";
+const ADDRESS: &str = "0x07968dab936c1bad187c60ce4082f307d030d780e91e694ae03aef16aba73f30";
+const PRIVATE_KEY: &str = "5d996aa76b3212142792d9130796cd2e11e3c445a93118c08414df4f66bc60ec";
+
+fn phrase_file(home: &Path) -> PathBuf {
+    let path = home.join("mnemonic.txt");
+    fs::write(&path, PHRASE).unwrap();
+    path
+}
+
+#[test]
+fn derivation_matches_the_published_test_wallet() {
+    let account = mnemonic::derive_account(PHRASE, 0).unwrap();
+
+    assert_eq!(mnemonic::derivation_path(0), "m/44'/637'/0'/0'/0'");
+    assert_eq!(account.address().to_hex_literal(), normalize(ADDRESS));
+    assert_eq!(hex::encode(account.private_key().to_bytes()), PRIVATE_KEY);
+}
+
+#[test]
+fn other_indexes_derive_other_accounts() {
+    let first = mnemonic::derive_account(PHRASE, 0).unwrap();
+    let second = mnemonic::derive_account(PHRASE, 1).unwrap();
+
+    assert_ne!(first.address(), second.address());
+}
+
+#[test]
+fn scan_lists_which_derived_accounts_exist() {
+    let node = MockNode::start();
+    node.set_balance(ADDRESS, 20_000);
+    let home = tempfile::tempdir().unwrap();
+    let phrase = phrase_file(home.path());
+
+    let output = stdout(&node.run(
+        home.path(),
+        &[
+            "scan",
+            "--mnemonic-file",
+            phrase.to_str().unwrap(),
+            "--count",
+            "2",
+        ],
+    ));
+
+    assert!(
+        output.contains(&format!("{}: sequence number 0", normalize(ADDRESS))),
+        "{}",
+        output
+    );
+    assert!(output.contains("no onchain account"), "{}", output);
+    assert!(
+        output.contains("1 of 2 derived addresses have onchain accounts"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn scan_fails_when_the_node_does() {
+    let node = MockNode::start();
+    let flaky = Flaky::start(
+        node.url(),
+        vec![Fault {
+            method: Method::GET,
+            path: "/accounts/",
+            status: 403,
+            times: 1,
+            when: When::Before,
+        }],
+    );
+    let home = tempfile::tempdir().unwrap();
+    let phrase = phrase_file(home.path());
+
+    let output = common::run(
+        flaky.url(),
+        Some(CHAIN_ID),
+        home.path(),
+        &["scan", "--mnemonic-file", phrase.to_str().unwrap()],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("Could not fetch account"), "{}", stderr);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("no onchain account"));
+}
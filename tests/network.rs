@@ -0,0 +1,52 @@
+// Network profiles against nodes on the wrong chain. A profile whose chain id is not pinned
+// must still refuse the chains it can never be on
+mod common;
+
+use common::{stdout, MockNode};
+use std::path::Path;
+use std::process::{Command, Output};
+
+const ADDRESS: &str = "0xa11ce";
+
+fn run_devnet(node: &MockNode, home: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aptos_client_test"))
+        .env_clear()
+        .env("HOME", home)
+        .arg("--keystore")
+        .arg(home.join("keystore"))
+        .args(["--network", "devnet", "--node-url", node.url()])
+        .args(["balance", ADDRESS])
+        .output()
+        .expect("failed to run binary")
+}
+
+#[test]
+fn devnet_accepts_whatever_chain_devnet_is_on() {
+    let node = MockNode::start_on_chain(47);
+    node.set_balance(ADDRESS, 20_000);
+    let home = tempfile::tempdir().unwrap();
+
+    assert!(stdout(&run_devnet(&node, home.path())).contains("0.0002 APT"));
+}
+
+#[test]
+fn devnet_profile_refuses_a_mainnet_or_testnet_node() {
+    for chain_id in [1, 2] {
+        let node = MockNode::start_on_chain(chain_id);
+        node.set_balance(ADDRESS, 20_000);
+        let home = tempfile::tempdir().unwrap();
+
+        let output = run_devnet(&node, home.path());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains(&format!(
+                "reports chain id {}, which belongs to another network",
+                chain_id
+            )),
+            "{}",
+            stderr
+        );
+    }
+}
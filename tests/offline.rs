@@ -0,0 +1,193 @@
+// The offline signing flow: build against the mock node, sign with no node at all, then submit
+mod common;
+
+use aptos_client_test::offline::TransactionFile;
+use aptos_sdk::types::LocalAccount;
+use common::{stderr, stdout, MockNode, CHAIN_ID};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+const OTHER_KEY: &str = "0x8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e45d3b9a0e";
+
+// Nothing listens on port 1, so anything that tries the network fails
+const DEAD_NODE: &str = "http://127.0.0.1:1";
+
+fn sender() -> String {
+    LocalAccount::from_private_key(SENDER_KEY, 0)
+        .unwrap()
+        .address()
+        .to_hex_literal()
+}
+
+fn build(node: &MockNode, home: &Path, extra: &[&str]) -> Output {
+    let unsigned = home.join("unsigned.json");
+    let sender = sender();
+    let mut args = vec!["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+    args.extend(extra);
+    args.extend(["build", "--from", &sender, "--to", RECIPIENT]);
+    args.extend(["--amount", "1000 octas"]);
+    args.extend(["--output-file", unsigned.to_str().unwrap()]);
+
+    node.run(home, &args)
+}
+
+// Seconds from now until the unsigned transaction expires
+fn expires_in(home: &Path) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let unsigned = TransactionFile::read(&home.join("unsigned.json")).unwrap();
+    unsigned.summary.expiration_timestamp_secs - now
+}
+
+fn sign(home: &Path, key: &str) -> Output {
+    let unsigned = home.join("unsigned.json");
+    let signed = home.join("signed.json");
+    common::run(
+        DEAD_NODE,
+        None,
+        home,
+        &[
+            "sign",
+            unsigned.to_str().unwrap(),
+            "--key",
+            key,
+            "--output-file",
+            signed.to_str().unwrap(),
+        ],
+    )
+}
+
+#[test]
+fn build_sign_and_submit_pays_the_recipient() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    node.set_balance(RECIPIENT, 0);
+    let home = tempfile::tempdir().unwrap();
+
+    let built = stdout(&build(&node, home.path(), &[]));
+    let description = format!("send 1000 octas of AptosCoin from {} to 0xb0b", sender());
+    assert!(built.contains(&description), "{}", built);
+    assert!(built.contains("Sequence number: 0"), "{}", built);
+
+    let signed = stdout(&sign(home.path(), SENDER_KEY));
+    assert!(signed.contains(&description), "{}", signed);
+    assert!(node.transactions_from(&sender()).is_empty());
+
+    let path = home.path().join("signed.json");
+    let submitted = stdout(&node.run(home.path(), &["submit", path.to_str().unwrap()]));
+    assert!(submitted.contains(&description), "{}", submitted);
+    assert_eq!(node.balance(RECIPIENT), 1_000);
+    assert_eq!(node.sequence_number(&sender()), Some(1));
+}
+
+#[test]
+fn files_carry_a_summary_for_review() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    let home = tempfile::tempdir().unwrap();
+    stdout(&build(&node, home.path(), &[]));
+    stdout(&sign(home.path(), SENDER_KEY));
+
+    let unsigned = TransactionFile::read(&home.path().join("unsigned.json")).unwrap();
+    let signed = TransactionFile::read(&home.path().join("signed.json")).unwrap();
+
+    assert_eq!(unsigned.summary, signed.summary);
+    assert_eq!(unsigned.summary.sender, sender());
+    assert_eq!(unsigned.summary.chain_id, CHAIN_ID);
+    assert_eq!(unsigned.summary.max_fee(), 1_000);
+    assert_eq!(
+        unsigned.summary.function,
+        "0x1::coin::transfer<0x1::aptos_coin::AptosCoin>"
+    );
+    assert_eq!(unsigned.summary.arguments, vec!["0xb0b", "1000"]);
+    assert!(unsigned.raw_transaction().is_ok());
+    assert!(unsigned.signed_transaction().is_err());
+    assert!(signed.signed_transaction().is_ok());
+}
+
+#[test]
+fn edited_summary_is_refused() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    let home = tempfile::tempdir().unwrap();
+    stdout(&build(&node, home.path(), &[]));
+
+    let path = home.path().join("unsigned.json");
+    let mut file: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    file["summary"]["description"] = Value::from("send 1 octas of AptosCoin to a friend");
+    fs::write(&path, file.to_string()).unwrap();
+    let output = sign(home.path(), SENDER_KEY);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("does not match its transaction"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(!home.path().join("signed.json").exists());
+}
+
+#[test]
+fn signing_with_another_key_is_refused() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    let home = tempfile::tempdir().unwrap();
+    stdout(&build(&node, home.path(), &[]));
+
+    let output = sign(home.path(), OTHER_KEY);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("but the transaction is sent from"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn unsigned_file_cannot_be_submitted() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    let home = tempfile::tempdir().unwrap();
+    stdout(&build(&node, home.path(), &[]));
+
+    let path = home.path().join("unsigned.json");
+    let output = node.run(home.path(), &["submit", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("run `sign` on it first"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(node.transactions_from(&sender()).is_empty());
+}
+
+#[test]
+fn build_leaves_an_hour_for_the_trip_to_the_signer() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    let home = tempfile::tempdir().unwrap();
+
+    stdout(&build(&node, home.path(), &[]));
+
+    assert!((3_590..=3_600).contains(&expires_in(home.path())));
+}
+
+#[test]
+fn build_expiration_can_be_set() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    let home = tempfile::tempdir().unwrap();
+
+    stdout(&build(&node, home.path(), &["--expiration-secs", "600"]));
+
+    assert!((590..=600).contains(&expires_in(home.path())));
+}
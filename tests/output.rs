@@ -0,0 +1,101 @@
+// Golden files pin the record schema scripts depend on. A change here is a breaking change
+// for anyone parsing the output, so update the files deliberately
+mod common;
+
+use common::{stdout, MockNode};
+use std::fs;
+
+const ADDRESS: &str = "0xa11ce";
+
+fn golden(name: &str) -> String {
+    fs::read_to_string(format!(
+        "{}/tests/golden/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+    .expect("missing golden file")
+}
+
+fn balance_output(format: &str) -> String {
+    let node = MockNode::start();
+    node.set_balance(ADDRESS, 20_000);
+    let home = tempfile::tempdir().unwrap();
+
+    stdout(&node.run(home.path(), &["balance", ADDRESS, "--output", format]))
+}
+
+#[test]
+fn balance_json_matches_golden() {
+    assert_eq!(balance_output("json"), golden("balance.json"));
+}
+
+#[test]
+fn balance_ndjson_matches_golden() {
+    assert_eq!(balance_output("ndjson"), golden("balance.ndjson"));
+}
+
+#[test]
+fn balance_csv_matches_golden() {
+    assert_eq!(balance_output("csv"), golden("balance.csv"));
+}
+
+#[test]
+fn balance_table_lists_label_address_and_amount() {
+    let output = balance_output("table");
+    let fields: Vec<&str> = output.split_whitespace().collect();
+    assert_eq!(fields, [ADDRESS, ADDRESS, "0.0002", "APT"]);
+}
+
+#[test]
+fn text_only_commands_refuse_structured_output() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+
+    let output = node.run(home.path(), &["accounts", "--output", "json"]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`accounts` only prints text"));
+}
+
+#[test]
+fn fund_json_is_a_single_document() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+
+    let output = stdout(&node.run(
+        home.path(),
+        &["fund", ADDRESS, "1000 octas", "--output", "json"],
+    ));
+    let records: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(records[0]["record"], "fund");
+    assert_eq!(records[0]["amount"], 1_000);
+}
+
+#[test]
+fn failed_command_still_finishes_its_json() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    // Nothing to pay with, so the transfer fails before it records anything
+    let sender_key = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+
+    let output = node.run(
+        home.path(),
+        &[
+            "transfer",
+            "--from",
+            sender_key,
+            "--to",
+            ADDRESS,
+            "--amount",
+            "1000 octas",
+            "--output",
+            "json",
+        ],
+    );
+
+    assert!(!output.status.success());
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(records, serde_json::json!([]));
+}
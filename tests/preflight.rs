@@ -0,0 +1,207 @@
+// Checks made before a transfer is signed, on their own and through the transfer command
+mod common;
+
+use aptos_client_test::pool::NodePool;
+use aptos_client_test::preflight::{self, PreflightError};
+use aptos_client_test::retry::RetryPolicy;
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use common::{normalize, stderr, stdout, MockNode, APTOS_COIN};
+use serde_json::Value;
+use std::process::Output;
+use url::Url;
+
+const SENDER: &str = "0xa11ce";
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+const MOON_COIN: &str = "0xcafe::moon_coin::MoonCoin";
+
+fn options(coin_type: &str) -> TransferOptions<'_> {
+    TransferOptions {
+        max_gas_amount: 1_000,
+        gas_unit_price: 2,
+        timeout_secs: 30,
+        coin_type,
+    }
+}
+
+// The failure `check` found, which must be one of its own
+async fn check(node: &MockNode, amount: u64, coin_type: &str) -> Option<PreflightError> {
+    let nodes = NodePool::new(vec![Url::parse(node.url()).unwrap()]);
+    let result = preflight::check(
+        &nodes,
+        &RetryPolicy::never(),
+        AccountAddress::from_hex_literal(SENDER).unwrap(),
+        AccountAddress::from_hex_literal(RECIPIENT).unwrap(),
+        amount,
+        &options(coin_type),
+        None,
+    )
+    .await;
+
+    result.err().map(|err| {
+        err.downcast::<PreflightError>()
+            .expect("not a preflight failure")
+    })
+}
+
+fn transfer(node: &MockNode, extra: &[&str]) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    let mut args = GAS.to_vec();
+    args.extend(["transfer", "--from", SENDER_KEY, "--to", RECIPIENT]);
+    args.extend(["--amount", "1000 octas"]);
+    args.extend(extra);
+
+    node.run(home.path(), &args)
+}
+
+#[tokio::test]
+async fn missing_sender_fails() {
+    let node = MockNode::start();
+    node.set_balance(RECIPIENT, 0);
+
+    assert_eq!(
+        check(&node, 1, APTOS_COIN).await,
+        Some(PreflightError::SenderNotFound("0xa11ce".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn apt_balance_must_cover_the_amount_and_max_gas() {
+    let node = MockNode::start();
+    node.set_balance(SENDER, 10_000);
+    node.set_balance(RECIPIENT, 0);
+
+    // 1000 max gas at 2 octas each
+    assert_eq!(check(&node, 8_000, APTOS_COIN).await, None);
+    assert_eq!(
+        check(&node, 8_001, APTOS_COIN).await,
+        Some(PreflightError::InsufficientApt {
+            address: "0xa11ce".to_string(),
+            balance: 10_000,
+            amount: 8_001,
+            fee: 2_000,
+        })
+    );
+}
+
+#[tokio::test]
+async fn other_coins_need_apt_for_gas_and_enough_of_the_coin() {
+    let node = MockNode::start();
+    node.set_coin_balance(SENDER, MOON_COIN, 500);
+    node.set_balance(SENDER, 1_999);
+    node.set_coin_balance(RECIPIENT, MOON_COIN, 0);
+
+    assert!(matches!(
+        check(&node, 500, MOON_COIN).await,
+        Some(PreflightError::InsufficientGas { fee: 2_000, .. })
+    ));
+
+    node.set_balance(SENDER, 2_000);
+    assert_eq!(check(&node, 500, MOON_COIN).await, None);
+    assert!(matches!(
+        check(&node, 501, MOON_COIN).await,
+        Some(PreflightError::InsufficientCoins { balance: 500, .. })
+    ));
+}
+
+#[tokio::test]
+async fn recipient_must_exist_and_hold_a_coin_store() {
+    let node = MockNode::start();
+    node.set_balance(SENDER, 10_000);
+    node.set_coin_balance(SENDER, MOON_COIN, 500);
+
+    assert_eq!(
+        check(&node, 100, APTOS_COIN).await,
+        Some(PreflightError::RecipientNotFound("0xb0b".to_string()))
+    );
+
+    node.set_balance(RECIPIENT, 0);
+    assert_eq!(check(&node, 100, APTOS_COIN).await, None);
+    assert_eq!(
+        check(&node, 100, MOON_COIN).await,
+        Some(PreflightError::CoinStoreNotRegistered {
+            address: "0xb0b".to_string(),
+            coin_type: MOON_COIN.to_string(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn auto_create_creates_a_missing_recipient() {
+    let node = MockNode::start();
+    node.set_balance(SENDER, 10_000);
+    let url = Url::parse(node.url()).unwrap();
+    let nodes = NodePool::new(vec![url.clone()]);
+    let faucet_client = FaucetClient::new(url.clone(), url);
+
+    let report = preflight::check(
+        &nodes,
+        &RetryPolicy::never(),
+        AccountAddress::from_hex_literal(SENDER).unwrap(),
+        AccountAddress::from_hex_literal(RECIPIENT).unwrap(),
+        100,
+        &options(APTOS_COIN),
+        Some(&faucet_client),
+    )
+    .await
+    .unwrap();
+
+    assert!(report.created_recipient);
+    assert_eq!(node.sequence_number(RECIPIENT), Some(0));
+}
+
+#[test]
+fn transfer_to_a_missing_recipient_is_never_sent() {
+    let node = MockNode::start();
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    let sender_address = sender.address().to_hex_literal();
+    node.set_balance(&sender_address, 10_000);
+
+    let output = transfer(&node, &[]);
+
+    assert_eq!(output.status.code(), Some(8), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("recipient 0xb0b has no onchain account"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(node.transactions_from(&sender_address).is_empty());
+    assert_eq!(node.balance(&sender_address), 10_000);
+}
+
+#[test]
+fn transfer_with_auto_create_pays_the_new_account() {
+    let node = MockNode::start();
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    node.set_balance(&sender.address().to_hex_literal(), 10_000);
+
+    let output = stdout(&transfer(&node, &["--auto-create"]));
+
+    assert!(
+        output.contains("Created recipient account 0xb0b"),
+        "{}",
+        output
+    );
+    assert_eq!(node.balance(RECIPIENT), 1_000);
+}
+
+#[test]
+fn created_recipient_is_a_record_in_structured_output() {
+    let node = MockNode::start();
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    node.set_balance(&sender.address().to_hex_literal(), 10_000);
+
+    let output = stdout(&transfer(&node, &["--auto-create", "--output", "ndjson"]));
+
+    let records: Vec<Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records[0]["record"], "create", "{}", output);
+    assert_eq!(records[0]["address"], normalize(RECIPIENT));
+    assert_eq!(records[1]["record"], "transfer", "{}", output);
+}
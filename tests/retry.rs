@@ -0,0 +1,220 @@
+// Retries of node and faucet calls, checked on their own and through the binary with a proxy
+// failing chosen requests in front of the mock node
+mod common;
+
+use anyhow::anyhow;
+use aptos_client_test::retry::RetryPolicy;
+use aptos_sdk::types::LocalAccount;
+use axum::http::Method;
+use common::flaky::{Fault, Flaky, When};
+use common::{stdout, MockNode, CHAIN_ID};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const ADDRESS: &str = "0xa11ce";
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(300),
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    }
+}
+
+fn fault(method: Method, path: &'static str, status: u16, times: usize, when: When) -> Fault {
+    Fault {
+        method,
+        path,
+        status,
+        times,
+        when,
+    }
+}
+
+// Run the binary through the proxy, retrying quickly so the tests stay fast
+fn run(flaky: &Flaky, home: &Path, args: &[&str]) -> std::process::Output {
+    let config = home.join("config.toml");
+    fs::write(
+        &config,
+        "[retry]\ninitial_backoff_ms = 10\nmax_backoff_ms = 50\n",
+    )
+    .unwrap();
+    let mut all = vec!["--config", config.to_str().unwrap()];
+    all.extend(args);
+
+    common::run(flaky.url(), Some(CHAIN_ID), home, &all)
+}
+
+#[test]
+fn unavailable_and_rate_limited_are_retryable() {
+    let policy = policy();
+
+    assert!(policy.is_retryable(&anyhow!("HTTP error 503 Service Unavailable: try again")));
+    assert!(policy.is_retryable(&anyhow!("HTTP status server error (502 Bad Gateway)")));
+    assert!(policy.is_retryable(&anyhow!(
+        "AptosError {{ code: 429, message: \"slow down\" }}"
+    )));
+    assert!(policy.is_retryable(
+        &anyhow!("error sending request for url (http://127.0.0.1:1/)").context("Could not fetch")
+    ));
+}
+
+#[test]
+fn client_errors_and_vm_failures_are_not_retryable() {
+    let policy = policy();
+
+    assert!(!policy.is_retryable(&anyhow!("HTTP error 404 Not Found: Account not found")));
+    assert!(!policy.is_retryable(&anyhow!(
+        "Invalid transaction: Type: Validation Code: SEQUENCE_NUMBER_TOO_OLD"
+    )));
+    assert!(!policy.is_retryable(&anyhow!("Move abort: code 65542")));
+    // A number after some other `code: ` is not an HTTP status
+    assert!(!policy.is_retryable(&anyhow!("Move abort in 0xcafe::market: error code: 503")));
+}
+
+#[test]
+fn status_codes_can_be_configured() {
+    let policy = RetryPolicy {
+        retryable_status_codes: vec![409],
+        ..policy()
+    };
+
+    assert!(policy.is_retryable(&anyhow!("HTTP error 409 Conflict")));
+    assert!(!policy.is_retryable(&anyhow!("HTTP error 503 Service Unavailable")));
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let policy = policy();
+
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(300));
+    assert_eq!(policy.backoff(40), Duration::from_millis(300));
+}
+
+#[test]
+fn jitter_stays_within_its_fraction() {
+    let policy = RetryPolicy {
+        jitter: 0.5,
+        ..policy()
+    };
+
+    for _ in 0..100 {
+        let backoff = policy.backoff(1);
+        assert!(backoff >= Duration::from_millis(50), "{:?}", backoff);
+        assert!(backoff <= Duration::from_millis(150), "{:?}", backoff);
+    }
+}
+
+#[test]
+fn last_attempt_gives_up() {
+    let policy = policy();
+    let err = anyhow!("HTTP error 503 Service Unavailable");
+
+    assert!(policy.delay(2, &err).is_some());
+    assert_eq!(policy.delay(3, &err), None);
+    assert_eq!(RetryPolicy::never().delay(1, &err), None);
+}
+
+#[test]
+fn balance_is_read_again_after_unavailable() {
+    let node = MockNode::start();
+    node.set_balance(ADDRESS, 20_000);
+    let flaky = Flaky::start(
+        node.url(),
+        vec![fault(Method::GET, "/accounts/", 503, 2, When::Before)],
+    );
+    let home = tempfile::tempdir().unwrap();
+
+    let output = stdout(&run(&flaky, home.path(), &["balance", ADDRESS]));
+
+    assert!(output.contains("0.0002 APT"), "{}", output);
+    assert_eq!(flaky.remaining(), 0);
+}
+
+#[test]
+fn max_attempts_of_one_never_retries() {
+    let node = MockNode::start();
+    node.set_balance(ADDRESS, 20_000);
+    let flaky = Flaky::start(
+        node.url(),
+        vec![fault(Method::GET, "/accounts/", 503, 1, When::Before)],
+    );
+    let home = tempfile::tempdir().unwrap();
+
+    let output = run(
+        &flaky,
+        home.path(),
+        &["--max-attempts", "1", "balance", ADDRESS],
+    );
+
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("503"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn not_found_is_not_retried() {
+    let node = MockNode::start();
+    let flaky = Flaky::start(node.url(), Vec::new());
+    let home = tempfile::tempdir().unwrap();
+
+    // The account does not exist, so its CoinStore lookup is answered with a 404 that no
+    // number of retries would change
+    run(&flaky, home.path(), &["balance", ADDRESS]);
+
+    assert_eq!(flaky.bodies(&Method::GET, "/accounts/").len(), 1);
+}
+
+#[test]
+fn faucet_is_called_again_when_it_refused_to_mint() {
+    let node = MockNode::start();
+    let flaky = Flaky::start(
+        node.url(),
+        vec![fault(Method::POST, "/mint", 503, 1, When::Before)],
+    );
+    let home = tempfile::tempdir().unwrap();
+
+    stdout(&run(&flaky, home.path(), &["fund", ADDRESS, "5000 octas"]));
+
+    assert_eq!(node.balance(ADDRESS), 5_000);
+    assert_eq!(flaky.bodies(&Method::POST, "/mint").len(), 2);
+}
+
+#[test]
+fn lost_submit_response_resubmits_the_same_transaction_once() {
+    let node = MockNode::start();
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    let sender_address = sender.address().to_hex_literal();
+    node.set_balance(&sender_address, 1_000_000);
+    node.set_balance(RECIPIENT, 0);
+    let flaky = Flaky::start(
+        node.url(),
+        vec![fault(Method::POST, "/transactions", 502, 1, When::After)],
+    );
+    let home = tempfile::tempdir().unwrap();
+    let mut args = GAS.to_vec();
+    args.extend(["transfer", "--from", SENDER_KEY, "--to", RECIPIENT]);
+    args.extend(["--amount", "1000 octas"]);
+
+    stdout(&run(&flaky, home.path(), &args));
+
+    // The first submission went through before its answer was replaced with a 502, so the
+    // retry sent the same signed bytes and the recipient was paid once
+    let bodies = flaky.bodies(&Method::POST, "/transactions");
+    assert_eq!(bodies.len(), 2);
+    assert!(bodies.iter().all(|body| body == &bodies[0]));
+    assert_eq!(node.transactions_from(&sender_address).len(), 1);
+    assert_eq!(node.balance(RECIPIENT), 1_000);
+    assert_eq!(node.sequence_number(&sender_address), Some(1));
+}
@@ -0,0 +1,118 @@
+// YAML scenarios run through the binary against the mock node and faucet
+mod common;
+
+use common::{stdout, MockNode, GAS_USED};
+use std::path::Path;
+use std::process::Output;
+
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+
+fn run_scenario(node: &MockNode, home: &Path, yaml: &str, extra: &[&str]) -> Output {
+    let file = home.join("scenario.yaml");
+    std::fs::write(&file, yaml).unwrap();
+
+    let mut args = GAS.to_vec();
+    args.push("run-scenario");
+    args.push(file.to_str().unwrap());
+    args.extend(extra);
+    node.run(home, &args)
+}
+
+fn pay_bob(assertions: &str) -> String {
+    format!(
+        "name: pay bob
+accounts: [alice, bob]
+steps:
+  - fund: {{ account: alice, amount: 10000 octas }}
+  - create: {{ account: bob }}
+  - transfer: {{ from: alice, to: bob, amount: 2500 octas }}
+{}",
+        assertions
+    )
+}
+
+#[test]
+fn passing_scenario_reports_every_step() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let yaml = pay_bob(&format!(
+        "  - assert_balance: {{ account: bob, amount: 2500 octas }}
+  - assert_balance: {{ account: alice, amount: {} octas }}
+",
+        10_000 - 2_500 - GAS_USED
+    ));
+
+    let output = stdout(&run_scenario(&node, home.path(), &yaml, &[]));
+
+    assert!(
+        output.contains("===== Scenario pay bob ====="),
+        "{}",
+        output
+    );
+    assert!(output.contains("5 of 5 steps passed"), "{}", output);
+    assert!(!output.contains("FAILED"), "{}", output);
+}
+
+#[test]
+fn failed_assertion_fails_the_command() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let yaml = pay_bob("  - assert_balance: { account: bob, amount: 0.1 APT }\n");
+
+    let output = run_scenario(&node, home.path(), &yaml, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("FAILED, actual 0.000025 APT"), "{}", stdout);
+    assert!(stderr.contains("Scenario 'pay bob' failed"), "{}", stderr);
+}
+
+#[test]
+fn failed_assertion_is_a_record_with_its_error() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let yaml = pay_bob("  - assert_balance: { account: bob, amount: 0.1 APT }\n");
+
+    let output = run_scenario(&node, home.path(), &yaml, &["--output", "ndjson"]);
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert!(!output.status.success());
+    let kinds: Vec<&str> = records
+        .iter()
+        .map(|record| record["record"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["fund", "create", "transfer", "assertion"]);
+    assert_eq!(records[2]["error"], serde_json::Value::Null);
+    assert_eq!(records[3]["label"], "bob");
+    assert_eq!(records[3]["amount"], 2_500);
+    assert_eq!(records[3]["error"], "expected 10000000 give or take 0");
+}
+
+#[test]
+fn tolerance_leaves_room_for_gas() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let yaml = pay_bob(
+        "  - assert_balance: { account: alice, amount: 7500 octas, tolerance: 100 octas }\n",
+    );
+
+    let output = stdout(&run_scenario(&node, home.path(), &yaml, &[]));
+
+    assert!(output.contains("4 of 4 steps passed"), "{}", output);
+}
+
+#[test]
+fn unknown_step_is_rejected() {
+    let node = MockNode::start();
+    let home = tempfile::tempdir().unwrap();
+    let yaml = pay_bob("  - teleport: { account: bob }\n");
+
+    let output = run_scenario(&node, home.path(), &yaml, &[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid scenario file"));
+}
@@ -0,0 +1,157 @@
+// Drives the scenario engine as a library against the mock node and faucet
+mod common;
+
+use aptos_client_test::pool::NodePool;
+use aptos_client_test::retry::RetryPolicy;
+use aptos_client_test::scenario::{Outcome, Runner, Scenario, Step, TransferSettings};
+use aptos_sdk::coin_client::TransferOptions;
+use aptos_sdk::rest_client::FaucetClient;
+use common::{MockNode, APTOS_COIN, GAS_USED};
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
+fn pay_bob(assertions: Vec<Step>) -> Scenario {
+    let mut steps = vec![
+        Step::Fund {
+            account: "alice".to_string(),
+            amount: 10_000,
+        },
+        Step::Create {
+            account: "bob".to_string(),
+        },
+        Step::Transfer {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            amount: 2_500,
+            settings: TransferSettings::default(),
+        },
+    ];
+    steps.extend(assertions);
+
+    Scenario {
+        name: "pay bob".to_string(),
+        accounts: vec!["alice".to_string(), "bob".to_string()],
+        steps,
+    }
+}
+
+fn assert_balance(account: &str, amount: u64) -> Step {
+    Step::AssertBalance {
+        account: account.to_string(),
+        amount,
+        tolerance: 0,
+        coin_type: None,
+    }
+}
+
+async fn run(node: &MockNode, scenario: &Scenario) -> aptos_client_test::scenario::Report {
+    let url = Url::parse(node.url()).unwrap();
+    let nodes = NodePool::new(vec![url.clone()]);
+    let runner = Runner {
+        nodes: &nodes,
+        faucet_client: Some(FaucetClient::new(url.clone(), url)),
+        options: TransferOptions {
+            max_gas_amount: 1_000,
+            gas_unit_price: 1,
+            timeout_secs: 30,
+            coin_type: APTOS_COIN,
+        },
+        wait_timeout: Duration::from_secs(10),
+        retry: RetryPolicy::never(),
+    };
+
+    runner.run(scenario, &mut HashMap::new()).await.unwrap()
+}
+
+#[tokio::test]
+async fn passing_scenario_reports_every_step() {
+    let node = MockNode::start();
+    let scenario = pay_bob(vec![
+        assert_balance("bob", 2_500),
+        assert_balance("alice", 10_000 - 2_500 - GAS_USED),
+    ]);
+
+    let report = run(&node, &scenario).await;
+
+    assert!(report.passed(&scenario));
+    assert_eq!(report.steps.len(), 5);
+    match &report.steps[2].outcome {
+        Outcome::Transferred {
+            receipt,
+            conservation,
+        } => {
+            assert_eq!(receipt.fee(), GAS_USED);
+            assert_eq!(conservation.before.sender, 10_000);
+            assert!(conservation.holds(), "{}", conservation);
+        }
+        other => panic!("expected a transfer, got {:?}", other),
+    }
+    let bob = report.accounts[1].1.to_hex_literal();
+    assert_eq!(node.balance(&bob), 2_500);
+}
+
+#[tokio::test]
+async fn balance_mismatch_fails_the_report_but_later_steps_still_run() {
+    let node = MockNode::start();
+    let scenario = pay_bob(vec![
+        assert_balance("bob", 9_999),
+        assert_balance("bob", 2_500),
+    ]);
+
+    let report = run(&node, &scenario).await;
+
+    assert!(!report.passed(&scenario));
+    assert_eq!(report.steps.len(), 5);
+    assert!(matches!(
+        report.steps[3].outcome,
+        Outcome::Balance {
+            expected: 9_999,
+            actual: 2_500,
+            ..
+        }
+    ));
+    assert!(report.steps[4].outcome.passed());
+}
+
+#[tokio::test]
+async fn failing_step_ends_the_run() {
+    let node = MockNode::start();
+    let mut scenario = pay_bob(vec![assert_balance("bob", 2_500)]);
+    // Bob has nothing to pay gas with, so the node rejects it before it runs
+    scenario.steps[2] = Step::Transfer {
+        from: "bob".to_string(),
+        to: "alice".to_string(),
+        amount: 1,
+        settings: TransferSettings::default(),
+    };
+
+    let report = run(&node, &scenario).await;
+
+    assert!(!report.passed(&scenario));
+    assert_eq!(report.steps.len(), 3);
+    assert!(matches!(report.steps[2].outcome, Outcome::Failed { .. }));
+}
+
+#[tokio::test]
+async fn undeclared_accounts_are_rejected_before_anything_runs() {
+    let node = MockNode::start();
+    let mut scenario = pay_bob(Vec::new());
+    scenario.steps.push(assert_balance("carol", 0));
+
+    let url = Url::parse(node.url()).unwrap();
+    let nodes = NodePool::new(vec![url]);
+    let runner = Runner {
+        nodes: &nodes,
+        faucet_client: None,
+        options: TransferOptions::default(),
+        wait_timeout: Duration::from_secs(10),
+        retry: RetryPolicy::never(),
+    };
+    let err = runner
+        .run(&scenario, &mut HashMap::new())
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("undeclared account 'carol'"));
+}
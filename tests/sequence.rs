@@ -0,0 +1,255 @@
+// Pipelined transfers from one sender through the sequence number manager, against the mock
+// node, which runs each sender's transactions in sequence number order as they arrive
+mod common;
+
+use aptos_client_test::pool::NodePool;
+use aptos_client_test::retry::RetryPolicy;
+use aptos_client_test::sequence::{InFlight, SequenceManager};
+use aptos_sdk::coin_client::{CoinClient, TransferOptions};
+use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use common::{MockNode, APTOS_COIN, GAS_USED};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+const SENDER_KEY: &str = "0x1f6c4c8e7bd0c5e2a4a9e0f1d2c3b4a5968778695a4b3c2d1e0f1a2b3c4d5e6f";
+
+const OPTIONS: TransferOptions<'static> = TransferOptions {
+    max_gas_amount: 1_000,
+    gas_unit_price: 1,
+    timeout_secs: 30,
+    coin_type: APTOS_COIN,
+};
+
+// A funded sender and an existing recipient
+async fn setup(node: &MockNode) -> (NodePool, AccountAddress) {
+    let url = Url::parse(node.url()).unwrap();
+    let nodes = NodePool::new(vec![url.clone()]);
+    let faucet_client = FaucetClient::new(url.clone(), url);
+
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    faucet_client
+        .fund(sender.address(), 1_000_000)
+        .await
+        .unwrap();
+    let recipient = LocalAccount::generate(&mut rand::rngs::OsRng).address();
+    faucet_client.create_account(recipient).await.unwrap();
+
+    (nodes, recipient)
+}
+
+async fn manager(nodes: &NodePool) -> SequenceManager {
+    let sender = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    SequenceManager::new(nodes, RetryPolicy::never(), sender)
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_submissions_all_commit() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = Arc::new(manager(&nodes).await);
+
+    let tasks: Vec<_> = (0..20)
+        .map(|_| {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                let in_flight = manager
+                    .submit_transfer(recipient, 100, &OPTIONS)
+                    .await
+                    .unwrap();
+                manager
+                    .wait(&in_flight, Duration::from_secs(10))
+                    .await
+                    .unwrap();
+                in_flight.sequence_number
+            })
+        })
+        .collect();
+    let mut sequence_numbers = Vec::new();
+    for task in tasks {
+        sequence_numbers.push(task.await.unwrap());
+    }
+
+    sequence_numbers.sort_unstable();
+    assert_eq!(sequence_numbers, (0..20).collect::<Vec<_>>());
+    assert_eq!(manager.next_sequence_number(), 20);
+    let sender = manager.address().to_hex_literal();
+    assert_eq!(node.sequence_number(&sender), Some(20));
+    assert_eq!(node.balance(&recipient.to_hex_literal()), 20 * 100);
+    assert_eq!(node.balance(&sender), 1_000_000 - 20 * (100 + GAS_USED));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn many_in_flight_before_any_wait() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = manager(&nodes).await;
+
+    let mut in_flight = Vec::new();
+    for _ in 0..10 {
+        in_flight.push(
+            manager
+                .submit_transfer(recipient, 1, &OPTIONS)
+                .await
+                .unwrap(),
+        );
+    }
+    assert_eq!(manager.next_sequence_number(), 10);
+
+    for transfer in in_flight.iter().rev() {
+        let receipt = manager
+            .wait(transfer, Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(receipt.success);
+    }
+    assert_eq!(node.balance(&recipient.to_hex_literal()), 10);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resyncs_after_sequence_number_too_old() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = manager(&nodes).await;
+
+    // The same key sends twice behind the manager's back
+    let rest_client = nodes.client();
+    let mut other = LocalAccount::from_private_key(SENDER_KEY, 0).unwrap();
+    for _ in 0..2 {
+        let pending = CoinClient::new(&rest_client)
+            .transfer(&mut other, recipient, 1, Some(OPTIONS))
+            .await
+            .unwrap();
+        rest_client.wait_for_transaction(&pending).await.unwrap();
+    }
+
+    let err = manager
+        .submit_transfer(recipient, 1, &OPTIONS)
+        .await
+        .err()
+        .unwrap();
+    assert!(format!("{:?}", err).contains("SEQUENCE_NUMBER_TOO_OLD"));
+    assert_eq!(manager.next_sequence_number(), 2);
+
+    let in_flight = manager
+        .submit_transfer(recipient, 1, &OPTIONS)
+        .await
+        .unwrap();
+    assert_eq!(in_flight.sequence_number, 2);
+    manager
+        .wait(&in_flight, Duration::from_secs(10))
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resyncs_after_expiration() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = manager(&nodes).await;
+
+    let first = manager
+        .submit_transfer(recipient, 1, &OPTIONS)
+        .await
+        .unwrap();
+    let expired = TransferOptions {
+        timeout_secs: 0,
+        ..OPTIONS
+    };
+    let err = manager
+        .submit_transfer(recipient, 1, &expired)
+        .await
+        .err()
+        .unwrap();
+    assert!(format!("{:?}", err).contains("TRANSACTION_EXPIRED"));
+    manager.wait(&first, Duration::from_secs(10)).await.unwrap();
+
+    // The expired transfer's sequence number is handed out again rather than left as a gap
+    assert_eq!(manager.next_sequence_number(), 1);
+    let next = manager
+        .submit_transfer(recipient, 1, &OPTIONS)
+        .await
+        .unwrap();
+    assert_eq!(next.sequence_number, 1);
+    manager.wait(&next, Duration::from_secs(10)).await.unwrap();
+    assert_eq!(
+        node.sequence_number(&manager.address().to_hex_literal()),
+        Some(2)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn other_rejections_give_the_sequence_number_back() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = manager(&nodes).await;
+
+    let unaffordable = TransferOptions {
+        max_gas_amount: 1_000_000,
+        gas_unit_price: 100,
+        ..OPTIONS
+    };
+    assert!(manager
+        .submit_transfer(recipient, 1, &unaffordable)
+        .await
+        .is_err());
+
+    assert_eq!(manager.next_sequence_number(), 0);
+}
+
+// Leave a gap at sequence number 0: the first transfer is refused after the second one was
+// already handed sequence number 1, so 0 cannot be taken back and the second one waits
+async fn submit_behind_a_gap(
+    manager: &SequenceManager,
+    recipient: AccountAddress,
+    options: &TransferOptions<'_>,
+) -> InFlight {
+    let unaffordable = TransferOptions {
+        max_gas_amount: 1_000_000,
+        gas_unit_price: 100,
+        ..OPTIONS
+    };
+    let (refused, stuck) = tokio::join!(
+        manager.submit_transfer(recipient, 1, &unaffordable),
+        manager.submit_transfer(recipient, 1, options)
+    );
+    assert!(refused.is_err());
+    let stuck = stuck.unwrap();
+    assert_eq!(stuck.sequence_number, 1);
+    assert_eq!(manager.next_sequence_number(), 2);
+
+    stuck
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn slow_transfer_is_not_resynced_away() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = manager(&nodes).await;
+    let stuck = submit_behind_a_gap(&manager, recipient, &OPTIONS).await;
+
+    // It has not expired and may still commit, so its sequence number stays taken
+    assert!(manager.wait(&stuck, Duration::from_secs(1)).await.is_err());
+
+    assert_eq!(manager.next_sequence_number(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn expired_transfer_is_resynced_away() {
+    let node = MockNode::start();
+    let (nodes, recipient) = setup(&node).await;
+    let manager = manager(&nodes).await;
+    let short = TransferOptions {
+        timeout_secs: 2,
+        ..OPTIONS
+    };
+    let stuck = submit_behind_a_gap(&manager, recipient, &short).await;
+
+    assert!(manager.wait(&stuck, Duration::from_secs(4)).await.is_err());
+
+    assert_eq!(manager.next_sequence_number(), 0);
+}
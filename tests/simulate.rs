@@ -0,0 +1,73 @@
+// `transfer --simulate`: reported in every output format, never sent, and failing the command
+// when the node predicts the transfer would fail
+mod common;
+
+use aptos_sdk::types::LocalAccount;
+use common::{normalize, stdout, MockNode, GAS_USED};
+use serde_json::Value;
+use std::process::Output;
+
+const RECIPIENT: &str = "0xb0b";
+const SENDER_KEY: &str = "0x5d3b9a0e8f1c2d4b6a7e9f0c1b2a3d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4";
+const GAS: [&str; 4] = ["--max-gas-amount", "1000", "--gas-unit-price", "1"];
+
+fn sender() -> String {
+    LocalAccount::from_private_key(SENDER_KEY, 0)
+        .unwrap()
+        .address()
+        .to_hex_literal()
+}
+
+fn simulate(node: &MockNode, extra: &[&str]) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    let mut args = GAS.to_vec();
+    args.extend(["transfer", "--from", SENDER_KEY, "--to", RECIPIENT]);
+    args.extend(["--amount", "1000 octas", "--simulate"]);
+    args.extend(extra);
+
+    node.run(home.path(), &args)
+}
+
+fn records(output: &str) -> Vec<Value> {
+    output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn simulation_is_reported_as_records_and_nothing_is_sent() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+    node.set_balance(RECIPIENT, 0);
+
+    let records = records(&stdout(&simulate(&node, &["--output", "ndjson"])));
+
+    assert_eq!(records[0]["record"], "simulation");
+    assert_eq!(records[0]["address"], normalize(RECIPIENT));
+    assert_eq!(records[0]["amount"], 1_000);
+    assert_eq!(records[0]["fee"], GAS_USED);
+    let simulated = |address: &str| {
+        records
+            .iter()
+            .find(|record| record["stage"] == "simulated" && record["address"] == address)
+            .map(|record| record["amount"].clone())
+    };
+    assert_eq!(simulated(&normalize(RECIPIENT)), Some(Value::from(1_000)));
+    assert!(node.transactions_from(&sender()).is_empty());
+    assert_eq!(node.balance(RECIPIENT), 0);
+}
+
+#[test]
+fn predicted_failure_fails_with_its_exit_code() {
+    let node = MockNode::start();
+    node.set_balance(&sender(), 10_000);
+
+    // The recipient has no account, so the transfer would abort
+    let output = simulate(&node, &["--output", "ndjson"]);
+
+    assert_eq!(output.status.code(), Some(8));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(records(&stdout)[0]["record"], "simulation");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Simulated transfer would fail"));
+}